@@ -0,0 +1,243 @@
+use std::collections::BTreeMap;
+
+use crate::{
+    diagnostics::{Conflict, Generator, Origin},
+    redirects::pattern_prefix,
+    render::RenderConfig,
+    robots::Robots,
+    Router,
+};
+
+impl Router {
+    /// Finds every pair of pages, redirects, fallbacks and files that produce the same output file.
+    ///
+    /// Output files are compared after mapping route paths to files, so `/blog` and `/blog/` conflict
+    /// since both are written to `blog.html`. Overlaps found while merging and nesting routers are included.
+    /// Redirects are only checked when `RenderConfig::redirect_page_renderer` is set, as they produce
+    /// no file otherwise. Pattern redirects do not produce a file of their own and are not checked.
+    /// Route lists are rendered to find the names of the files they are split into.
+    ///
+    /// # Arguments
+    ///
+    /// * `config` - The rendering configuration options, which determine the names of fallback pages
+    ///   and generated files.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use pagebake::render::RenderConfig;
+    /// use pagebake::{get, redirect, Router};
+    ///
+    /// let router = Router::new()
+    ///     .route("/blog", get(|| "<h1>Blog</h1>".to_owned()))
+    ///     .merge(Router::new().route("/blog/", redirect("/")));
+    ///
+    /// let conflicts = router.conflicts(&RenderConfig::default());
+    /// assert_eq!(conflicts[0].file, "blog.html");
+    /// ```
+    ///
+    /// Without redirect pages, as on Netlify, the redirect does not conflict with the page:
+    ///
+    /// ```rust
+    /// use pagebake::hosts::HostProfile;
+    /// use pagebake::{get, redirect, Router};
+    ///
+    /// let router = Router::new()
+    ///     .route("/blog", get(|| "<h1>Blog</h1>".to_owned()))
+    ///     .route("/blog/", redirect("/blog"));
+    ///
+    /// let config = HostProfile::Netlify.render_config();
+    /// assert!(router.conflicts(&config).is_empty());
+    ///
+    /// let output = router.render_to_map(config).unwrap();
+    /// assert_eq!(output.extra_files["_redirects"], "/blog/ /blog 302");
+    /// ```
+    ///
+    /// Of generated files with the same name, the one configured first is written:
+    ///
+    /// ```rust
+    /// use pagebake::diagnostics::{Conflict, Diagnostic, Generator, Level, Origin};
+    /// use pagebake::redirects::RedirectList;
+    /// use pagebake::render::RenderConfig;
+    /// use pagebake::{get, redirect, Router};
+    ///
+    /// let router = Router::new()
+    ///     .route("/", get(|| "<h1>Home</h1>".to_owned()))
+    ///     .route("/old", redirect("/"));
+    ///
+    /// let config = RenderConfig {
+    ///     redirect_lists: vec![
+    ///         RedirectList::for_cloudflare_pages(),
+    ///         RedirectList::for_netlify(),
+    ///     ],
+    ///     conflicts: Level::Warn,
+    ///     ..Default::default()
+    /// };
+    ///
+    /// let output = router.render_to_map(config).unwrap();
+    /// assert_eq!(output.extra_files["_redirects"], "/old / 302");
+    /// assert_eq!(
+    ///     output.warnings,
+    ///     vec![Diagnostic::Conflict(Conflict {
+    ///         file: "_redirects".to_owned(),
+    ///         first: Origin::GeneratedFile {
+    ///             generator: Generator::RedirectList(0),
+    ///             file: "_redirects".to_owned(),
+    ///         },
+    ///         second: Origin::GeneratedFile {
+    ///             generator: Generator::RedirectList(1),
+    ///             file: "_redirects".to_owned(),
+    ///         },
+    ///     })]
+    /// );
+    /// ```
+    pub fn conflicts(&self, config: &RenderConfig) -> Vec<Conflict> {
+        let mut sections: Vec<String> = self.sections.iter().cloned().collect();
+        sections.sort();
+
+        let route_list_files = config
+            .route_lists
+            .iter()
+            .map(|list| {
+                let (files, index) = list.render_files(&self.route_list_entries(list), &sections);
+                files
+                    .into_iter()
+                    .map(|(name, _)| name)
+                    .chain(index)
+                    .collect()
+            })
+            .collect();
+
+        self.find_conflicts(config, route_list_files)
+    }
+
+    /// Finds conflicts as described in [`Router::conflicts`], given the names of the files of each route list.
+    pub(crate) fn find_conflicts(
+        &self,
+        config: &RenderConfig,
+        route_list_files: Vec<Vec<String>>,
+    ) -> Vec<Conflict> {
+        let mut origins: Vec<Origin> = self
+            .routes
+            .keys()
+            .map(|path| Origin::Page(path.to_owned()))
+            .chain(
+                self.redirects
+                    .keys()
+                    .filter(|_| config.redirect_page_renderer.is_some())
+                    .filter(|source| pattern_prefix(source).is_none())
+                    .map(|source| Origin::Redirect(source.to_owned())),
+            )
+            .chain(
+                self.fallbacks
+                    .keys()
+                    .map(|prefix| Origin::Fallback(prefix.to_owned())),
+            )
+            .chain(generated_files(config, route_list_files))
+            .chain(
+                config
+                    .static_files
+                    .iter()
+                    .map(|path| Origin::StaticFile(path.to_owned())),
+            )
+            .collect();
+        origins.sort();
+
+        let mut files = BTreeMap::<String, Vec<Origin>>::new();
+        for origin in origins {
+            files
+                .entry(origin.file(&config.fallback_page_name))
+                .or_default()
+                .push(origin);
+        }
+
+        let mut conflicts: Vec<Conflict> = self
+            .overlaps
+            .iter()
+            .map(|(first, second)| Conflict {
+                file: second.file(&config.fallback_page_name),
+                first: first.to_owned(),
+                second: second.to_owned(),
+            })
+            .collect();
+
+        for (file, origins) in files {
+            let (first, rest) = origins.split_first().unwrap();
+
+            conflicts.extend(rest.iter().map(|second| Conflict {
+                file: file.to_owned(),
+                first: first.to_owned(),
+                second: second.to_owned(),
+            }));
+        }
+
+        conflicts.sort_by(|a, b| a.file.cmp(&b.file));
+        conflicts
+    }
+}
+
+/// Returns the files generated by `config`, given the names of the files of each route list,
+/// in the order of the generators.
+fn generated_files(config: &RenderConfig, route_list_files: Vec<Vec<String>>) -> Vec<Origin> {
+    let mut files: Vec<(Generator, String)> = vec![];
+
+    files.extend(
+        config
+            .redirect_lists
+            .iter()
+            .enumerate()
+            .map(|(i, list)| (Generator::RedirectList(i), list.file_name.to_owned())),
+    );
+    files.extend(
+        config
+            .header_lists
+            .iter()
+            .enumerate()
+            .map(|(i, list)| (Generator::HeaderList(i), list.file_name.to_owned())),
+    );
+    files.extend(
+        route_list_files
+            .into_iter()
+            .enumerate()
+            .flat_map(|(i, names)| {
+                names
+                    .into_iter()
+                    .map(move |name| (Generator::RouteList(i), name))
+            }),
+    );
+    files.extend(
+        config
+            .route_manifests
+            .iter()
+            .enumerate()
+            .map(|(i, manifest)| (Generator::RouteManifest(i), manifest.file_name.to_owned())),
+    );
+    if config.robots.is_some() {
+        files.push((Generator::Robots, Robots::FILE_NAME.to_owned()));
+    }
+    if let Some(index) = &config.search_index {
+        files.push((Generator::SearchIndex, index.file_name.to_owned()));
+        if let Some(open_search) = &index.open_search {
+            files.push((Generator::OpenSearch, open_search.file_name.to_owned()));
+        }
+    }
+    files.extend(
+        config
+            .post_render_lists
+            .iter()
+            .enumerate()
+            .map(|(i, list)| (Generator::PostRenderList(i), list.file_name.to_owned())),
+    );
+    files.extend(
+        config
+            .host
+            .iter()
+            .flat_map(|host| host.files())
+            .map(|(name, _)| (Generator::Host, name.to_owned())),
+    );
+
+    files
+        .into_iter()
+        .map(|(generator, file)| Origin::GeneratedFile { generator, file })
+        .collect()
+}
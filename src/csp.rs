@@ -0,0 +1,458 @@
+use std::collections::HashMap;
+
+use crate::{headers::HeaderRule, html::Scanner};
+
+/// How the Content-Security-Policy of each page is delivered.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CspOutput {
+    /// Sends the policy as a `Content-Security-Policy` header, through header lists such as
+    /// `HeaderList::for_headers_file`, or redirect lists that include headers.
+    Header,
+    /// Inserts the policy into each page as a `<meta http-equiv="Content-Security-Policy">` tag.
+    /// Browsers ignore the `frame-ancestors`, `report-uri` and `sandbox` directives in this form.
+    MetaTag,
+}
+
+/// Configuration for generating a Content-Security-Policy for each page,
+/// which allows the inline scripts and styles of the page by their hashes.
+///
+/// After rendering, every page is scanned for inline `<script>` and `<style>` elements,
+/// and the SHA-256 hash of each one is appended to the `script-src` or `style-src` directive
+/// of the page's policy. When the policy has no such directive, it is added with the value of
+/// `default-src`, or `'self'` if there is none. Scripts with a `src` attribute are loaded from
+/// their URL and are not hashed, and neither are `style` and event handler attributes.
+///
+/// Redirect and fallback pages are included, so the script of `Redirect::base_redirect_page`
+/// is allowed without maintaining its hash by hand.
+///
+/// When delivered as a header, a `Content-Security-Policy` header set for a single page, such as with
+/// `Router::header`, is merged into the generated policy of the page, with its directives replacing
+/// those of the configuration. Such a header set for a pattern would be sent along with the generated
+/// policies, and is reported as `Diagnostic::CspHeaderConflict`.
+///
+/// # Examples
+///
+/// ```rust
+/// use pagebake::csp::{ContentSecurityPolicy, CspOutput};
+/// use pagebake::render::RenderConfig;
+/// use pagebake::{Router, get};
+///
+/// let router = Router::new().route(
+///     "/",
+///     get(|| "<head></head><script>alert(1)</script>".to_owned()),
+/// );
+///
+/// let config = RenderConfig {
+///     content_security_policy: Some(
+///         ContentSecurityPolicy::new(CspOutput::MetaTag).directive("default-src", "'self'"),
+///     ),
+///     ..Default::default()
+/// };
+///
+/// let output = router.render_to_map(config).unwrap();
+/// assert_eq!(
+///     output.pages["/"],
+///     "<head><meta http-equiv=\"Content-Security-Policy\" content=\"default-src 'self'; \
+///      script-src 'self' 'sha256-bhHHL3z2vDgxUt0W3dWQOrprscmda2Y5pLsLg4GF+pI='\">\
+///      </head><script>alert(1)</script>"
+/// );
+/// ```
+#[derive(Debug, Clone)]
+pub struct ContentSecurityPolicy {
+    /// Names and values of the directives of the policy, such as `default-src` and `'self'`.
+    pub directives: Vec<(String, String)>,
+    /// How the policy is delivered.
+    pub output: CspOutput,
+}
+
+impl ContentSecurityPolicy {
+    /// Creates a `ContentSecurityPolicy` configuration without directives,
+    /// so that each policy only restricts scripts and styles when the page has inline ones.
+    pub fn new(output: CspOutput) -> Self {
+        Self {
+            directives: vec![],
+            output,
+        }
+    }
+
+    /// Adds a directive to the policy, such as `img-src` with a value of `'self' https://images.example.com`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the name is not a directive name, or if the value contains a `;`, a `,` or line breaks.
+    pub fn directive(mut self, name: &str, value: &str) -> Self {
+        if name.is_empty() || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-') {
+            panic!("Invalid directive name `{name}`");
+        }
+        if value.contains([';', ',', '\r', '\n']) {
+            panic!("Value of directive `{name}` must not contain `;`, `,` or line breaks");
+        }
+
+        self.directives.push((name.to_owned(), value.to_owned()));
+        self
+    }
+
+    /// Returns the policy of a page, with the hashes of its inline scripts and styles.
+    ///
+    /// The directives of `base`, a policy set for the page by hand, replace those of the configuration.
+    pub(crate) fn policy(&self, html: &str, base: Option<&str>) -> String {
+        let mut directives = self.directives.clone();
+        for directive in base.into_iter().flat_map(|policy| policy.split(';')) {
+            let directive = directive.trim();
+            if directive.is_empty() {
+                continue;
+            }
+            let (name, value) = directive.split_once(' ').unwrap_or((directive, ""));
+            let (name, value) = (name.to_ascii_lowercase(), value.trim().to_owned());
+
+            match directives
+                .iter_mut()
+                .find(|(existing, _)| *existing == name)
+            {
+                Some((_, existing)) => *existing = value,
+                None => directives.push((name, value)),
+            }
+        }
+
+        for (element, directive) in [("script", "script-src"), ("style", "style-src")] {
+            let mut hashes = Vec::<String>::new();
+            for content in inline_contents(html, element) {
+                let hash = format!("'sha256-{}'", base64(&sha256(content.as_bytes())));
+                if !hashes.contains(&hash) {
+                    hashes.push(hash);
+                }
+            }
+            if hashes.is_empty() {
+                continue;
+            }
+
+            match directives.iter_mut().find(|(name, _)| name == directive) {
+                Some((_, value)) => value.push_str(&format!(" {}", hashes.join(" "))),
+                None => {
+                    let fallback = directives
+                        .iter()
+                        .find(|(name, _)| name == "default-src")
+                        .map_or("'self'", |(_, value)| value);
+                    directives.push((
+                        directive.to_owned(),
+                        format!("{fallback} {}", hashes.join(" ")),
+                    ));
+                }
+            }
+        }
+
+        directives
+            .iter()
+            .map(|(name, value)| match value.as_str() {
+                "" => name.to_owned(),
+                value => format!("{name} {value}"),
+            })
+            .collect::<Vec<String>>()
+            .join("; ")
+    }
+
+    /// Applies the policy to the rendered pages, returning the header rules of the pages
+    /// when the policy is delivered as a header.
+    ///
+    /// `header_policies` are the `Content-Security-Policy` headers set by hand, keyed by page path,
+    /// which are merged into the generated policies when delivered as a header.
+    pub(crate) fn apply<'a>(
+        &self,
+        pages: impl Iterator<Item = (&'a String, &'a mut String)>,
+        header_policies: &HashMap<String, String>,
+    ) -> Vec<HeaderRule> {
+        let mut rules = vec![];
+
+        for (path, html) in pages {
+            let base = match self.output {
+                CspOutput::Header => header_policies.get(path).map(String::as_str),
+                CspOutput::MetaTag => None,
+            };
+            let policy = self.policy(html, base);
+            if policy.is_empty() {
+                continue;
+            }
+
+            match self.output {
+                CspOutput::Header => rules.push(HeaderRule {
+                    path: path.to_owned(),
+                    headers: vec![(CSP_HEADER.to_owned(), policy)],
+                }),
+                CspOutput::MetaTag => {
+                    // Quotes in source expressions are kept readable, as the attribute is double-quoted.
+                    let tag = format!(
+                        "<meta http-equiv=\"Content-Security-Policy\" content=\"{}\">",
+                        policy.replace('&', "&amp;").replace('"', "&quot;")
+                    );
+                    html.insert_str(head_position(html), &tag);
+                }
+            }
+        }
+
+        rules
+    }
+}
+
+/// Name of the header that carries the policy.
+pub(crate) const CSP_HEADER: &str = "Content-Security-Policy";
+
+/// Returns the contents of the inline elements of the given name, in the order they appear.
+fn inline_contents<'a>(html: &'a str, element: &str) -> Vec<&'a str> {
+    let mut scanner = Scanner::new(html);
+    let mut contents = vec![];
+
+    while let Some(tag) = scanner.next_tag() {
+        if tag.name != element || tag.closing {
+            continue;
+        }
+
+        let content_end = scanner.skip_to_closing(element);
+        let has_src = tag
+            .attributes
+            .split(|c: char| c.is_ascii_whitespace())
+            .any(|attribute| attribute == "src" || attribute.starts_with("src="));
+        if !has_src {
+            contents.push(&html[tag.end..content_end]);
+        }
+    }
+
+    contents
+}
+
+/// Returns the position right after the opening `<head>` tag of a page.
+/// If there is none, returns the position after the doctype, or the start of the page.
+fn head_position(html: &str) -> usize {
+    let mut scanner = Scanner::new(html);
+    let mut doctype = None;
+
+    while let Some(tag) = scanner.next_tag() {
+        match tag.name.as_str() {
+            "head" if !tag.closing => return tag.end,
+            "!doctype" if tag.start == html.len() - html.trim_start().len() => {
+                doctype = Some(tag.end);
+            }
+            "script" | "style" if !tag.closing => {
+                scanner.skip_to_closing(&tag.name);
+            }
+            _ => {}
+        }
+    }
+
+    doctype.unwrap_or(0)
+}
+
+/// Round constants of SHA-256.
+const K: [u32; 64] = [
+    0x428a2f98, 0x71374491, 0xb5c0fbcf, 0xe9b5dba5, 0x3956c25b, 0x59f111f1, 0x923f82a4, 0xab1c5ed5,
+    0xd807aa98, 0x12835b01, 0x243185be, 0x550c7dc3, 0x72be5d74, 0x80deb1fe, 0x9bdc06a7, 0xc19bf174,
+    0xe49b69c1, 0xefbe4786, 0x0fc19dc6, 0x240ca1cc, 0x2de92c6f, 0x4a7484aa, 0x5cb0a9dc, 0x76f988da,
+    0x983e5152, 0xa831c66d, 0xb00327c8, 0xbf597fc7, 0xc6e00bf3, 0xd5a79147, 0x06ca6351, 0x14292967,
+    0x27b70a85, 0x2e1b2138, 0x4d2c6dfc, 0x53380d13, 0x650a7354, 0x766a0abb, 0x81c2c92e, 0x92722c85,
+    0xa2bfe8a1, 0xa81a664b, 0xc24b8b70, 0xc76c51a3, 0xd192e819, 0xd6990624, 0xf40e3585, 0x106aa070,
+    0x19a4c116, 0x1e376c08, 0x2748774c, 0x34b0bcb5, 0x391c0cb3, 0x4ed8aa4a, 0x5b9cca4f, 0x682e6ff3,
+    0x748f82ee, 0x78a5636f, 0x84c87814, 0x8cc70208, 0x90befffa, 0xa4506ceb, 0xbef9a3f7, 0xc67178f2,
+];
+
+/// Computes the SHA-256 digest of `data`.
+fn sha256(data: &[u8]) -> [u8; 32] {
+    let mut state: [u32; 8] = [
+        0x6a09e667, 0xbb67ae85, 0x3c6ef372, 0xa54ff53a, 0x510e527f, 0x9b05688c, 0x1f83d9ab,
+        0x5be0cd19,
+    ];
+
+    let mut message = data.to_vec();
+    message.push(0x80);
+    while message.len() % 64 != 56 {
+        message.push(0);
+    }
+    message.extend_from_slice(&(data.len() as u64 * 8).to_be_bytes());
+
+    for block in message.chunks(64) {
+        let mut w = [0u32; 64];
+        for (i, word) in block.chunks(4).enumerate() {
+            w[i] = u32::from_be_bytes([word[0], word[1], word[2], word[3]]);
+        }
+        for i in 16..64 {
+            let s0 = w[i - 15].rotate_right(7) ^ w[i - 15].rotate_right(18) ^ (w[i - 15] >> 3);
+            let s1 = w[i - 2].rotate_right(17) ^ w[i - 2].rotate_right(19) ^ (w[i - 2] >> 10);
+            w[i] = w[i - 16]
+                .wrapping_add(s0)
+                .wrapping_add(w[i - 7])
+                .wrapping_add(s1);
+        }
+
+        let [mut a, mut b, mut c, mut d, mut e, mut f, mut g, mut h] = state;
+        for i in 0..64 {
+            let s1 = e.rotate_right(6) ^ e.rotate_right(11) ^ e.rotate_right(25);
+            let ch = (e & f) ^ (!e & g);
+            let t1 = h
+                .wrapping_add(s1)
+                .wrapping_add(ch)
+                .wrapping_add(K[i])
+                .wrapping_add(w[i]);
+            let s0 = a.rotate_right(2) ^ a.rotate_right(13) ^ a.rotate_right(22);
+            let maj = (a & b) ^ (a & c) ^ (b & c);
+            let t2 = s0.wrapping_add(maj);
+
+            h = g;
+            g = f;
+            f = e;
+            e = d.wrapping_add(t1);
+            d = c;
+            c = b;
+            b = a;
+            a = t1.wrapping_add(t2);
+        }
+
+        for (value, added) in state.iter_mut().zip([a, b, c, d, e, f, g, h]) {
+            *value = value.wrapping_add(added);
+        }
+    }
+
+    let mut digest = [0u8; 32];
+    for (bytes, value) in digest.chunks_mut(4).zip(state) {
+        bytes.copy_from_slice(&value.to_be_bytes());
+    }
+    digest
+}
+
+/// Encodes `data` in standard base64 with padding.
+fn base64(data: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+    let mut encoded = String::with_capacity(data.len().div_ceil(3) * 4);
+    for chunk in data.chunks(3) {
+        let bytes = [
+            chunk[0],
+            *chunk.get(1).unwrap_or(&0),
+            *chunk.get(2).unwrap_or(&0),
+        ];
+        let bits = u32::from_be_bytes([0, bytes[0], bytes[1], bytes[2]]);
+
+        for i in 0..4 {
+            if i <= chunk.len() {
+                encoded.push(ALPHABET[(bits >> (18 - 6 * i) & 0x3f) as usize] as char);
+            } else {
+                encoded.push('=');
+            }
+        }
+    }
+
+    encoded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hex(digest: [u8; 32]) -> String {
+        digest.iter().map(|byte| format!("{byte:02x}")).collect()
+    }
+
+    #[test]
+    fn sha256_known_answers() {
+        let cases = [
+            (
+                0,
+                "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855",
+            ),
+            (
+                55,
+                "9f4390f8d30c2dd92ec9f095b65e2b9ae9b0a925a5258e241c9f1e910f734318",
+            ),
+            (
+                56,
+                "b35439a4ac6f0948b6d6f9e3c6af0f5f590ce20f1bde7090ef7970686ec6738a",
+            ),
+            (
+                64,
+                "ffe054fe7ae0cb6dc65c3af9b61d5209f439851db43d0ba5997337df154668eb",
+            ),
+            (
+                65,
+                "635361c48bb9eab14198e76ea8ab7f1a41685d6ad62aa9146d301d4f17eb0ae0",
+            ),
+            (
+                200,
+                "c2a908d98f5df987ade41b5fce213067efbcc21ef2240212a41e54b5e7c28ae5",
+            ),
+        ];
+
+        for (length, expected) in cases {
+            assert_eq!(hex(sha256(&vec![b'a'; length])), expected, "{length} bytes");
+        }
+    }
+
+    #[test]
+    fn sha256_nist_vectors() {
+        assert_eq!(
+            hex(sha256(b"abc")),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+        assert_eq!(
+            hex(sha256(
+                b"abcdbcdecdefdefgefghfghighijhijkijkljklmklmnlmnomnopnopq"
+            )),
+            "248d6a61d20638b8e5c026930c3e6039a33ce45964ff2167f6ecedd419db06c1"
+        );
+    }
+
+    #[test]
+    fn base64_known_answers() {
+        let cases = [
+            ("", ""),
+            ("f", "Zg=="),
+            ("fo", "Zm8="),
+            ("foo", "Zm9v"),
+            ("foob", "Zm9vYg=="),
+            ("fooba", "Zm9vYmE="),
+            ("foobar", "Zm9vYmFy"),
+        ];
+
+        for (data, expected) in cases {
+            assert_eq!(base64(data.as_bytes()), expected, "{data:?}");
+        }
+        assert_eq!(base64(&[0xfb, 0xff, 0xbf]), "+/+/");
+    }
+
+    #[test]
+    fn inline_contents_skips_external_scripts() {
+        let html = "<script src=\"/app.js\"></script><script type=\"module\" src=x></script>\
+                    <script data-src=\"x\">a()</script>";
+        assert_eq!(inline_contents(html, "script"), vec!["a()"]);
+    }
+
+    #[test]
+    fn inline_contents_matches_tags_case_insensitively() {
+        let html = "<SCRIPT>a()</SCRIPT><Style type=\"text/css\">p {}</sTyLe>";
+        assert_eq!(inline_contents(html, "script"), vec!["a()"]);
+        assert_eq!(inline_contents(html, "style"), vec!["p {}"]);
+    }
+
+    #[test]
+    fn inline_contents_skips_other_elements() {
+        let html = "<scripts>a()</scripts><scripting>b()</scripting><script>c()</script>";
+        assert_eq!(inline_contents(html, "script"), vec!["c()"]);
+    }
+
+    #[test]
+    fn inline_contents_reads_unclosed_elements_to_the_end() {
+        assert_eq!(inline_contents("<script>a()", "script"), vec!["a()"]);
+        assert!(inline_contents("<script", "script").is_empty());
+    }
+
+    #[test]
+    fn inline_contents_keeps_multibyte_content() {
+        let html = "<p>é</p><SCRIPT>'é€😀'</SCRIPT><script>😀</script>";
+        assert_eq!(inline_contents(html, "script"), vec!["'é€😀'", "😀"]);
+    }
+
+    #[test]
+    fn policy_hashes_multibyte_content() {
+        let policy = ContentSecurityPolicy::new(CspOutput::Header);
+        assert_eq!(
+            policy.policy("<script>é€😀</script>", None),
+            "script-src 'self' 'sha256-35Imkn/VcsHuZu7IXeG7E5SXYUiZ825OkEdMtx9u+dA='"
+        );
+    }
+}
@@ -0,0 +1,277 @@
+use std::{error::Error, fmt, io};
+
+use crate::render::page_file;
+
+/// How problems found by a check during rendering are reported.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Level {
+    /// The check is skipped.
+    Allow,
+    /// Problems are returned as warnings alongside the rendered output.
+    Warn,
+    /// Problems abort rendering with a `RenderError`.
+    Deny,
+}
+
+/// A problem found while preparing a site for rendering.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Diagnostic {
+    /// A redirect points to a path that is neither a page, a redirect nor a known file.
+    DanglingRedirect { source: String, target: String },
+    /// Two parts of the site produce the same output file.
+    Conflict(Conflict),
+    /// Following redirects leads back to a path that was already visited.
+    /// The cycle starts and ends with the same path.
+    RedirectCycle { cycle: Vec<String> },
+    /// A chain of redirects is longer than `RenderConfig::max_redirect_chain`.
+    /// The chain starts with the redirect source and ends with its final target.
+    LongRedirectChain { chain: Vec<String> },
+    /// A redirect of the root router has a relative target, which is only allowed in nested routers.
+    RelativeRedirect { source: String, target: String },
+    /// A redirect is left out of a redirect list whose host cannot serve it.
+    UnsupportedRedirect {
+        file: String,
+        source: String,
+        reason: String,
+    },
+    /// The host set in `RenderConfig::host` cannot serve a feature of the site.
+    UnsupportedFeature { host: String, feature: String },
+    /// A `Content-Security-Policy` header set for a pattern would be sent along with
+    /// the policies generated for the pages under it.
+    CspHeaderConflict { path: String },
+    /// The Content-Security-Policy is delivered as a header, but no header list or redirect list includes headers.
+    CspWithoutHeaderList,
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Diagnostic::DanglingRedirect { source, target } => {
+                write!(
+                    f,
+                    "Redirect `{source}` points to `{target}`, which does not exist"
+                )
+            }
+            Diagnostic::Conflict(conflict) => conflict.fmt(f),
+            Diagnostic::RedirectCycle { cycle } => {
+                write!(f, "Cycle in redirects: {}", cycle.join(" -> "))
+            }
+            Diagnostic::LongRedirectChain { chain } => write!(
+                f,
+                "Chain of {} redirects: {}",
+                chain.len() - 1,
+                chain.join(" -> ")
+            ),
+            Diagnostic::RelativeRedirect { source, target } => write!(
+                f,
+                "Redirect `{source}` has the relative target `{target}`, which is only allowed in nested routers"
+            ),
+            Diagnostic::UnsupportedRedirect {
+                file,
+                source,
+                reason,
+            } => write!(
+                f,
+                "Redirect `{source}` is left out of `{file}`, as {reason}"
+            ),
+            Diagnostic::UnsupportedFeature { host, feature } => {
+                write!(f, "{host} does not support {feature}")
+            }
+            Diagnostic::CspHeaderConflict { path } => write!(
+                f,
+                "The `Content-Security-Policy` header of `{path}` would be sent along with the generated policies of its pages"
+            ),
+            Diagnostic::CspWithoutHeaderList => write!(
+                f,
+                "The Content-Security-Policy is delivered as a header, but no header list or redirect list includes headers"
+            ),
+        }
+    }
+}
+
+/// The part of a site that an output file originates from.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Origin {
+    /// A page registered at the given route path.
+    Page(String),
+    /// A redirect from the given source path.
+    Redirect(String),
+    /// A fallback page for the given path prefix.
+    Fallback(String),
+    /// A file generated by the configuration, such as a redirect or route list.
+    GeneratedFile {
+        /// The part of the configuration that generates the file.
+        generator: Generator,
+        /// The name of the file.
+        file: String,
+    },
+    /// A file served alongside the site, as declared in `RenderConfig::static_files`.
+    StaticFile(String),
+}
+
+impl Origin {
+    /// Returns the output file produced by this origin, relative to the output directory.
+    pub(crate) fn file(&self, fallback_page_name: &str) -> String {
+        match self {
+            Origin::Page(path) | Origin::Redirect(path) => page_file(path),
+            Origin::Fallback(prefix) => {
+                let separator = if prefix.ends_with('/') { "" } else { "/" };
+                page_file(&format!("{prefix}{separator}{fallback_page_name}"))
+            }
+            Origin::GeneratedFile { file: path, .. } | Origin::StaticFile(path) => {
+                path.trim_start_matches('/').to_owned()
+            }
+        }
+    }
+
+    /// Returns the origin with `prefix` prepended to its path, as done when nesting routers.
+    pub(crate) fn prefixed(self, prefix: &str) -> Self {
+        match self {
+            Origin::Page(path) => Origin::Page(format!("{prefix}{path}")),
+            Origin::Redirect(path) => Origin::Redirect(format!("{prefix}{path}")),
+            Origin::Fallback(path) => Origin::Fallback(format!("{prefix}{path}")),
+            origin => origin,
+        }
+    }
+}
+
+impl fmt::Display for Origin {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Origin::Page(path) => write!(f, "page `{path}`"),
+            Origin::Redirect(path) => write!(f, "redirect `{path}`"),
+            Origin::Fallback(prefix) => write!(f, "fallback for `{prefix}`"),
+            Origin::GeneratedFile { generator, .. } => write!(f, "{generator}"),
+            Origin::StaticFile(path) => write!(f, "static file `{path}`"),
+        }
+    }
+}
+
+/// The part of a `RenderConfig` that generates a file.
+///
+/// Generators are ordered as the fields of `RenderConfig`, and entries of the same field by their index,
+/// so that the first generator of a file is the one configured first.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Generator {
+    /// The entry of `RenderConfig::redirect_lists` with the given index.
+    RedirectList(usize),
+    /// The entry of `RenderConfig::header_lists` with the given index.
+    HeaderList(usize),
+    /// The entry of `RenderConfig::route_lists` with the given index.
+    RouteList(usize),
+    /// The entry of `RenderConfig::route_manifests` with the given index.
+    RouteManifest(usize),
+    /// `RenderConfig::robots`.
+    Robots,
+    /// `RenderConfig::search_index`.
+    SearchIndex,
+    /// The OpenSearch description of `RenderConfig::search_index`.
+    OpenSearch,
+    /// The entry of `RenderConfig::post_render_lists` with the given index.
+    PostRenderList(usize),
+    /// `RenderConfig::host`.
+    Host,
+}
+
+impl fmt::Display for Generator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Generator::RedirectList(i) => write!(f, "`redirect_lists[{i}]`"),
+            Generator::HeaderList(i) => write!(f, "`header_lists[{i}]`"),
+            Generator::RouteList(i) => write!(f, "`route_lists[{i}]`"),
+            Generator::RouteManifest(i) => write!(f, "`route_manifests[{i}]`"),
+            Generator::Robots => write!(f, "`robots`"),
+            Generator::SearchIndex => write!(f, "`search_index`"),
+            Generator::OpenSearch => write!(f, "`search_index.open_search`"),
+            Generator::PostRenderList(i) => write!(f, "`post_render_lists[{i}]`"),
+            Generator::Host => write!(f, "`host`"),
+        }
+    }
+}
+
+/// Two parts of a site that produce the same output file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Conflict {
+    /// The output file, relative to the output directory.
+    pub file: String,
+    /// The first origin producing the file, which is the one written when conflicts are not denied.
+    pub first: Origin,
+    /// Another origin producing the same file.
+    pub second: Origin,
+}
+
+impl fmt::Display for Conflict {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "`{}` is produced by both {} and {}, of which {} is kept",
+            self.file, self.first, self.second, self.first
+        )
+    }
+}
+
+/// Error returned when rendering a site fails.
+#[derive(Debug)]
+pub enum RenderError {
+    /// The site has redirect cycles, or checks configured with `Level::Deny` found problems with it.
+    Invalid(Vec<Diagnostic>),
+    /// Writing the rendered output failed.
+    Io(io::Error),
+}
+
+impl fmt::Display for RenderError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RenderError::Invalid(diagnostics) => {
+                write!(f, "Site is invalid:")?;
+                for diagnostic in diagnostics {
+                    write!(f, "\n- {diagnostic}")?;
+                }
+                Ok(())
+            }
+            RenderError::Io(error) => write!(f, "Failed to write output: {error}"),
+        }
+    }
+}
+
+impl Error for RenderError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            RenderError::Invalid(_) => None,
+            RenderError::Io(error) => Some(error),
+        }
+    }
+}
+
+impl From<io::Error> for RenderError {
+    fn from(error: io::Error) -> Self {
+        RenderError::Io(error)
+    }
+}
+
+/// Collects the problems found by checks according to their configured `Level`.
+#[derive(Default)]
+pub(crate) struct Report {
+    pub(crate) warnings: Vec<Diagnostic>,
+    pub(crate) errors: Vec<Diagnostic>,
+}
+
+impl Report {
+    /// Records the problems found by a check with the given level.
+    pub(crate) fn add(&mut self, level: Level, diagnostics: Vec<Diagnostic>) {
+        match level {
+            Level::Allow => {}
+            Level::Warn => self.warnings.extend(diagnostics),
+            Level::Deny => self.errors.extend(diagnostics),
+        }
+    }
+
+    /// Returns the warnings, or an error if any problem was denied.
+    pub(crate) fn finish(self) -> Result<Vec<Diagnostic>, RenderError> {
+        if self.errors.is_empty() {
+            Ok(self.warnings)
+        } else {
+            Err(RenderError::Invalid(self.errors))
+        }
+    }
+}
@@ -0,0 +1,672 @@
+use crate::{
+    escape,
+    routes::{RouteEntry, RouteFilter, RouteList},
+};
+
+/// Feed metadata of a page, which makes it an item of the feeds generated by [`RouteList::rss`],
+/// [`RouteList::atom`] and [`RouteList::json_feed`].
+///
+/// # Examples
+///
+/// ```rust
+/// use pagebake::feeds::FeedItem;
+/// use pagebake::routes::RouteMeta;
+/// use pagebake::{Router, get};
+///
+/// let router = Router::new().route(
+///     "/blog/hello",
+///     get(|| "<h1>Hello</h1>".to_owned()).with_meta(RouteMeta {
+///         feed: Some(FeedItem {
+///             title: "Hello".to_owned(),
+///             date: "2024-05-01T09:00:00Z".to_owned(),
+///             description: Some("Our first post.".to_owned()),
+///             content: Some("<p>Hello, world!</p>".to_owned()),
+///             authors: vec!["Jane Doe".to_owned()],
+///             ..Default::default()
+///         }),
+///         ..Default::default()
+///     }),
+/// );
+/// ```
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct FeedItem {
+    /// The title of the item.
+    pub title: String,
+    /// The publication date in RFC 3339 format, such as `2024-05-01` or `2024-05-01T09:00:00+02:00`.
+    pub date: String,
+    /// A short summary of the item.
+    pub description: Option<String>,
+    /// The full content of the item as HTML. RSS feeds do not include it.
+    pub content: Option<String>,
+    /// The names of the authors of the item. When empty, the authors of the feed are assumed.
+    pub authors: Vec<String>,
+    /// The date of the last significant change to the item in RFC 3339 format.
+    /// When `None`, the publication date is used.
+    pub updated: Option<String>,
+}
+
+/// Configuration of a feed, shared by all feed formats.
+pub struct Feed {
+    /// The origin of the site, used to compute absolute URLs of items. A trailing `/` is ignored.
+    pub origin_url: String,
+    /// The title of the feed.
+    pub title: String,
+    /// A description of the feed.
+    pub description: String,
+    /// The names of the authors of the feed.
+    /// Atom feeds require authors for the feed, or for each of its items.
+    pub authors: Vec<String>,
+    /// When set, only pages under this prefix (e.g. `/blog`) are included in the feed.
+    pub prefix: Option<String>,
+    /// The largest number of items in the feed. The most recent items are kept.
+    pub limit: Option<usize>,
+}
+
+impl Feed {
+    /// Creates a feed of every page of the site with feed metadata.
+    pub fn new(origin_url: String, title: String, description: String) -> Self {
+        Self {
+            origin_url,
+            title,
+            description,
+            authors: vec![],
+            prefix: None,
+            limit: None,
+        }
+    }
+
+    /// Limits the feed to pages under `prefix`, such as the mount path of a nested router.
+    pub fn prefix(mut self, prefix: &str) -> Self {
+        self.prefix = Some(prefix.trim_end_matches('/').to_owned());
+        self
+    }
+
+    /// Sets the authors of the feed.
+    pub fn authors(mut self, authors: Vec<String>) -> Self {
+        self.authors = authors;
+        self
+    }
+
+    /// Limits the feed to the `limit` most recent items.
+    pub fn limit(mut self, limit: usize) -> Self {
+        self.limit = Some(limit);
+        self
+    }
+
+    /// Returns the URL of the page the feed belongs to.
+    fn link(&self) -> String {
+        format!(
+            "{}{}/",
+            escape::origin(&self.origin_url),
+            escape::url(self.prefix.as_deref().unwrap_or(""))
+        )
+    }
+
+    /// Returns the items of the feed, most recent first.
+    fn items(&self, routes: Vec<RouteEntry>) -> Vec<Item> {
+        let mut items: Vec<Item> = routes
+            .into_iter()
+            .filter(|route| match &self.prefix {
+                Some(prefix) => {
+                    route.path == *prefix || route.path.starts_with(&format!("{prefix}/"))
+                }
+                None => true,
+            })
+            .filter_map(|route| {
+                let item = route.meta.feed?;
+                let date = DateTime::parse(&item.date).unwrap();
+                let updated = match &item.updated {
+                    Some(updated) => DateTime::parse(updated).unwrap(),
+                    None => DateTime::parse(&item.date).unwrap(),
+                };
+
+                Some(Item {
+                    url: format!(
+                        "{}{}",
+                        escape::origin(&self.origin_url),
+                        escape::url(&route.path)
+                    ),
+                    item,
+                    date,
+                    updated,
+                })
+            })
+            .collect();
+
+        items.sort_by(|a, b| {
+            b.date
+                .timestamp()
+                .cmp(&a.date.timestamp())
+                .then_with(|| a.url.cmp(&b.url))
+        });
+        if let Some(limit) = self.limit {
+            items.truncate(limit);
+        }
+
+        items
+    }
+}
+
+/// An item of a feed with its parsed dates.
+struct Item {
+    /// The absolute URL of the page.
+    url: String,
+    item: FeedItem,
+    date: DateTime,
+    updated: DateTime,
+}
+
+impl RouteList {
+    /// Creates a `RouteList` configuration for RSS 2.0 feeds.
+    ///
+    /// The generated file will be named `rss.xml` and contain the pages with feed metadata, most recent first.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use pagebake::feeds::Feed;
+    /// use pagebake::routes::RouteList;
+    ///
+    /// let feed = Feed::new(
+    ///     "https://example.com".to_owned(),
+    ///     "Blog".to_owned(),
+    ///     "News and updates".to_owned(),
+    /// );
+    ///
+    /// let rss = RouteList {
+    ///     file_name: "blog/rss.xml",
+    ///     ..RouteList::rss(feed.prefix("/blog").limit(20))
+    /// };
+    /// ```
+    pub fn rss(feed: Feed) -> Self {
+        RouteList {
+            file_name: "rss.xml",
+            content_renderer: Box::new(move |routes: Vec<RouteEntry>| {
+                let mut content = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+                content.push_str("<rss version=\"2.0\">\n  <channel>\n");
+                content.push_str(&format!(
+                    "    <title>{}</title>\n    <link>{}</link>\n    <description>{}</description>\n",
+                    escape::xml(&feed.title),
+                    escape::xml(&feed.link()),
+                    escape::xml(&feed.description)
+                ));
+
+                for Item {
+                    url, item, date, ..
+                } in feed.items(routes)
+                {
+                    let url = escape::xml(&url);
+
+                    content.push_str("    <item>\n");
+                    content.push_str(&format!(
+                        "      <title>{}</title>\n      <link>{url}</link>\n      <guid>{url}</guid>\n",
+                        escape::xml(&item.title)
+                    ));
+                    content.push_str(&format!("      <pubDate>{}</pubDate>\n", date.to_rfc822()));
+                    if let Some(description) = &item.description {
+                        content.push_str(&format!(
+                            "      <description>{}</description>\n",
+                            escape::xml(description)
+                        ));
+                    }
+                    content.push_str("    </item>\n");
+                }
+
+                content.push_str("  </channel>\n</rss>");
+                content
+            }),
+            include_redirects: false,
+            filter: RouteFilter::default(),
+            sitemap: false,
+            index: None,
+        }
+    }
+
+    /// Creates a `RouteList` configuration for Atom 1.0 feeds.
+    ///
+    /// The generated file will be named `atom.xml` and contain the pages with feed metadata, most recent first.
+    /// The feed is updated at the most recent `updated` date of its items.
+    pub fn atom(feed: Feed) -> Self {
+        RouteList {
+            file_name: "atom.xml",
+            content_renderer: Box::new(move |routes: Vec<RouteEntry>| {
+                let items = feed.items(routes);
+                let updated = items
+                    .iter()
+                    .map(|item| &item.updated)
+                    .max_by_key(|updated| updated.timestamp())
+                    .map_or("1970-01-01T00:00:00Z".to_owned(), |u| u.to_rfc3339());
+                let link = escape::xml(&feed.link());
+
+                let mut content = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+                content.push_str("<feed xmlns=\"http://www.w3.org/2005/Atom\">\n");
+                content.push_str(&format!(
+                    "  <title>{}</title>\n  <subtitle>{}</subtitle>\n  <link href=\"{link}\"/>\n  <id>{link}</id>\n  <updated>{updated}</updated>\n",
+                    escape::xml(&feed.title),
+                    escape::xml(&feed.description)
+                ));
+                content.push_str(&atom_authors(&feed.authors, "  "));
+
+                for Item {
+                    url,
+                    item,
+                    date,
+                    updated,
+                } in items
+                {
+                    let url = escape::xml(&url);
+
+                    content.push_str("  <entry>\n");
+                    content.push_str(&format!(
+                        "    <title>{}</title>\n    <link href=\"{url}\"/>\n    <id>{url}</id>\n",
+                        escape::xml(&item.title)
+                    ));
+                    content.push_str(&format!(
+                        "    <published>{}</published>\n    <updated>{}</updated>\n",
+                        date.to_rfc3339(),
+                        updated.to_rfc3339()
+                    ));
+                    content.push_str(&atom_authors(&item.authors, "    "));
+                    if let Some(description) = &item.description {
+                        content.push_str(&format!(
+                            "    <summary>{}</summary>\n",
+                            escape::xml(description)
+                        ));
+                    }
+                    if let Some(html) = &item.content {
+                        content.push_str(&format!(
+                            "    <content type=\"html\">{}</content>\n",
+                            escape::xml(html)
+                        ));
+                    }
+                    content.push_str("  </entry>\n");
+                }
+
+                content.push_str("</feed>");
+                content
+            }),
+            include_redirects: false,
+            filter: RouteFilter::default(),
+            sitemap: false,
+            index: None,
+        }
+    }
+
+    /// Creates a `RouteList` configuration for [JSON Feed 1.1](https://www.jsonfeed.org/version/1.1/) feeds.
+    ///
+    /// The generated file will be named `feed.json` and contain the pages with feed metadata, most recent first.
+    /// Items without content use their description as plain text content.
+    pub fn json_feed(feed: Feed) -> Self {
+        RouteList {
+            file_name: "feed.json",
+            content_renderer: Box::new(move |routes: Vec<RouteEntry>| {
+                let mut content = String::from("{\n");
+                content.push_str("  \"version\": \"https://jsonfeed.org/version/1.1\",\n");
+                content.push_str(&format!(
+                    "  \"title\": {},\n  \"home_page_url\": {},\n  \"description\": {},\n",
+                    escape::json(&feed.title),
+                    escape::json(&feed.link()),
+                    escape::json(&feed.description)
+                ));
+                if !feed.authors.is_empty() {
+                    content.push_str(&format!(
+                        "  \"authors\": {},\n",
+                        json_authors(&feed.authors)
+                    ));
+                }
+
+                let items = feed
+                    .items(routes)
+                    .into_iter()
+                    .map(
+                        |Item {
+                             url,
+                             item,
+                             date,
+                             updated,
+                         }| {
+                            let mut fields = vec![
+                                format!("\"id\": {}", escape::json(&url)),
+                                format!("\"url\": {}", escape::json(&url)),
+                                format!("\"title\": {}", escape::json(&item.title)),
+                            ];
+                            match (&item.content, &item.description) {
+                                (Some(html), description) => {
+                                    fields
+                                        .push(format!("\"content_html\": {}", escape::json(html)));
+                                    if let Some(description) = description {
+                                        fields.push(format!(
+                                            "\"summary\": {}",
+                                            escape::json(description)
+                                        ));
+                                    }
+                                }
+                                (None, description) => fields.push(format!(
+                                    "\"content_text\": {}",
+                                    escape::json(description.as_deref().unwrap_or(""))
+                                )),
+                            }
+                            fields.push(format!(
+                                "\"date_published\": {}",
+                                escape::json(&date.to_rfc3339())
+                            ));
+                            fields.push(format!(
+                                "\"date_modified\": {}",
+                                escape::json(&updated.to_rfc3339())
+                            ));
+                            if !item.authors.is_empty() {
+                                fields
+                                    .push(format!("\"authors\": {}", json_authors(&item.authors)));
+                            }
+
+                            format!("    {{\n      {}\n    }}", fields.join(",\n      "))
+                        },
+                    )
+                    .collect::<Vec<String>>();
+
+                if items.is_empty() {
+                    content.push_str("  \"items\": []\n}");
+                } else {
+                    content.push_str(&format!("  \"items\": [\n{}\n  ]\n}}", items.join(",\n")));
+                }
+                content
+            }),
+            include_redirects: false,
+            filter: RouteFilter::default(),
+            sitemap: false,
+            index: None,
+        }
+    }
+}
+
+/// Formats `<author>` elements of an Atom feed, indented by `indent`.
+fn atom_authors(authors: &[String], indent: &str) -> String {
+    authors
+        .iter()
+        .map(|name| {
+            format!(
+                "{indent}<author>\n{indent}  <name>{}</name>\n{indent}</author>\n",
+                escape::xml(name)
+            )
+        })
+        .collect()
+}
+
+/// Formats the `authors` array of a JSON feed.
+fn json_authors(authors: &[String]) -> String {
+    let authors = authors
+        .iter()
+        .map(|name| format!("{{ \"name\": {} }}", escape::json(name)))
+        .collect::<Vec<String>>();
+
+    format!("[{}]", authors.join(", "))
+}
+
+/// Checks that the feed metadata of a page is valid, returning a description of the problem otherwise.
+pub(crate) fn check_feed_item(item: &FeedItem) -> Result<(), String> {
+    for date in std::iter::once(&item.date).chain(&item.updated) {
+        if DateTime::parse(date).is_none() {
+            return Err(format!(
+                "Feed item dates must be in RFC 3339 format, such as `2024-05-01T09:00:00Z`. `{date}` is not"
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+/// A date and time parsed from an RFC 3339 string.
+struct DateTime {
+    year: i64,
+    month: u32,
+    day: u32,
+    hour: u32,
+    minute: u32,
+    second: u32,
+    /// Offset from UTC in minutes.
+    offset: i32,
+}
+
+impl DateTime {
+    /// Parses a full RFC 3339 date and time, or a date alone, which is taken as midnight UTC.
+    fn parse(text: &str) -> Option<Self> {
+        let (date, time) = match text.split_once(['T', 't', ' ']) {
+            Some((date, time)) => (date, Some(time)),
+            None => (text, None),
+        };
+
+        let [year, month, day] = numbers(date, '-', [4, 2, 2])?;
+        let mut date_time = DateTime {
+            year: year.into(),
+            month,
+            day,
+            hour: 0,
+            minute: 0,
+            second: 0,
+            offset: 0,
+        };
+        if !(1..=12).contains(&month) || day == 0 || day > date_time.days_in_month() {
+            return None;
+        }
+
+        let Some(time) = time else {
+            return Some(date_time);
+        };
+
+        let (time, offset) = if let Some(time) = time.strip_suffix(['Z', 'z']) {
+            (time, 0)
+        } else {
+            let (time, offset) = time.split_at(time.rfind(['+', '-'])?);
+            let [hours, minutes] = numbers(&offset[1..], ':', [2, 2])?;
+            if hours > 23 || minutes > 59 {
+                return None;
+            }
+            let minutes = (hours * 60 + minutes) as i32;
+            let offset = if offset.starts_with('-') {
+                -minutes
+            } else {
+                minutes
+            };
+            (time, offset)
+        };
+
+        let time = match time.split_once('.') {
+            Some((time, fraction))
+                if !fraction.is_empty() && fraction.bytes().all(|b| b.is_ascii_digit()) =>
+            {
+                time
+            }
+            Some(_) => return None,
+            None => time,
+        };
+        let [hour, minute, second] = numbers(time, ':', [2, 2, 2])?;
+        if hour > 23 || minute > 59 || second > 60 {
+            return None;
+        }
+
+        date_time.hour = hour;
+        date_time.minute = minute;
+        date_time.second = second;
+        date_time.offset = offset;
+        Some(date_time)
+    }
+
+    fn is_leap_year(&self) -> bool {
+        self.year % 4 == 0 && (self.year % 100 != 0 || self.year % 400 == 0)
+    }
+
+    fn days_in_month(&self) -> u32 {
+        match self.month {
+            2 if self.is_leap_year() => 29,
+            2 => 28,
+            4 | 6 | 9 | 11 => 30,
+            _ => 31,
+        }
+    }
+
+    /// Returns the number of days since 1970-01-01.
+    fn days(&self) -> i64 {
+        let year = if self.month <= 2 {
+            self.year - 1
+        } else {
+            self.year
+        };
+        let era = year.div_euclid(400);
+        let year_of_era = year - era * 400;
+        let month = i64::from((self.month + 9) % 12);
+        let day_of_year = (153 * month + 2) / 5 + i64::from(self.day) - 1;
+        let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+
+        era * 146_097 + day_of_era - 719_468
+    }
+
+    /// Returns the number of seconds since 1970-01-01T00:00:00Z.
+    fn timestamp(&self) -> i64 {
+        self.days() * 86_400 + i64::from(self.hour * 3600 + self.minute * 60 + self.second)
+            - i64::from(self.offset) * 60
+    }
+
+    /// Formats the offset from UTC as `+hhmm`, or as `+hh:mm` with a `separator`.
+    fn offset(&self, separator: &str) -> String {
+        let sign = if self.offset < 0 { '-' } else { '+' };
+        let offset = self.offset.abs();
+        format!("{sign}{:02}{separator}{:02}", offset / 60, offset % 60)
+    }
+
+    /// Formats the date as used in Atom and JSON feeds, such as `2024-05-01T09:00:00+00:00`.
+    fn to_rfc3339(&self) -> String {
+        format!(
+            "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}{}",
+            self.year,
+            self.month,
+            self.day,
+            self.hour,
+            self.minute,
+            self.second,
+            self.offset(":")
+        )
+    }
+
+    /// Formats the date as used in RSS, such as `Wed, 01 May 2024 09:00:00 +0000`.
+    fn to_rfc822(&self) -> String {
+        const WEEKDAYS: [&str; 7] = ["Thu", "Fri", "Sat", "Sun", "Mon", "Tue", "Wed"];
+        const MONTHS: [&str; 12] = [
+            "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec",
+        ];
+
+        format!(
+            "{}, {:02} {} {:04} {:02}:{:02}:{:02} {}",
+            WEEKDAYS[self.days().rem_euclid(7) as usize],
+            self.day,
+            MONTHS[self.month as usize - 1],
+            self.year,
+            self.hour,
+            self.minute,
+            self.second,
+            self.offset("")
+        )
+    }
+}
+
+/// Splits `text` by `separator` into numbers with exactly the given number of digits each.
+fn numbers<const N: usize>(text: &str, separator: char, digits: [usize; N]) -> Option<[u32; N]> {
+    let mut numbers = [0; N];
+    let mut parts = text.split(separator);
+
+    for (number, digits) in numbers.iter_mut().zip(digits) {
+        let part = parts.next()?;
+        if part.len() != digits || !part.bytes().all(|b| b.is_ascii_digit()) {
+            return None;
+        }
+        *number = part.parse().ok()?;
+    }
+
+    match parts.next() {
+        Some(_) => None,
+        None => Some(numbers),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rfc3339(text: &str) -> Option<String> {
+        DateTime::parse(text).map(|date| date.to_rfc3339())
+    }
+
+    fn rfc822(text: &str) -> Option<String> {
+        DateTime::parse(text).map(|date| date.to_rfc822())
+    }
+
+    #[test]
+    fn dates_alone_are_midnight_utc() {
+        assert_eq!(
+            rfc3339("2024-05-01").as_deref(),
+            Some("2024-05-01T00:00:00+00:00")
+        );
+        assert_eq!(
+            rfc822("2024-05-01").as_deref(),
+            Some("Wed, 01 May 2024 00:00:00 +0000")
+        );
+        assert_eq!(
+            rfc822("1969-12-31").as_deref(),
+            Some("Wed, 31 Dec 1969 00:00:00 +0000")
+        );
+    }
+
+    #[test]
+    fn offsets_are_kept() {
+        assert_eq!(
+            rfc3339("2024-05-01T09:30:00+02:00").as_deref(),
+            Some("2024-05-01T09:30:00+02:00")
+        );
+        assert_eq!(
+            rfc822("2024-05-01T09:30:00+02:00").as_deref(),
+            Some("Wed, 01 May 2024 09:30:00 +0200")
+        );
+        assert_eq!(
+            rfc822("2024-05-01t09:30:00.250-05:30").as_deref(),
+            Some("Wed, 01 May 2024 09:30:00 -0530")
+        );
+        assert_eq!(
+            rfc822("2024-05-01 09:30:00z").as_deref(),
+            Some("Wed, 01 May 2024 09:30:00 +0000")
+        );
+        assert_eq!(
+            rfc3339("2024-05-01 09:30:00z").as_deref(),
+            Some("2024-05-01T09:30:00+00:00")
+        );
+
+        let with_offset = DateTime::parse("2024-05-01T09:30:00+02:00").unwrap();
+        let utc = DateTime::parse("2024-05-01T07:30:00Z").unwrap();
+        assert_eq!(with_offset.timestamp(), utc.timestamp());
+        assert_eq!(DateTime::parse("1970-01-01").unwrap().timestamp(), 0);
+    }
+
+    #[test]
+    fn leap_days_are_checked() {
+        assert!(DateTime::parse("2024-02-29").is_some());
+        assert!(DateTime::parse("2000-02-29").is_some());
+        assert!(DateTime::parse("2023-02-29").is_none());
+        assert!(DateTime::parse("1900-02-29").is_none());
+    }
+
+    #[test]
+    fn invalid_dates_are_rejected() {
+        for text in [
+            "",
+            "2024-5-01",
+            "2024-13-01",
+            "2024-04-31",
+            "2024-05-01T24:00:00Z",
+            "2024-05-01T09:00Z",
+            "2024-05-01T09:00:00",
+            "2024-05-01T09:00:00+2:00",
+            "2024-05-01T09:00:00+24:00",
+            "2024-05-01T09:00:00.Z",
+        ] {
+            assert!(DateTime::parse(text).is_none(), "{text}");
+        }
+    }
+}
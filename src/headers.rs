@@ -0,0 +1,104 @@
+use crate::{escape, redirects::pattern_prefix, routes::RouteFilter};
+
+/// HTTP headers that are sent for a path, or for every path under a prefix.
+///
+/// Rules are passed to header list renderers, and to redirect list renderers for hosts that read
+/// redirects and headers from the same file, such as the `config.toml` of Static Web Server.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HeaderRule {
+    /// The path that the headers are sent for. A path ending in `/*` matches every path under its prefix.
+    pub path: String,
+    /// Names and values of the headers, in the order they were added.
+    pub headers: Vec<(String, String)>,
+}
+
+impl HeaderRule {
+    /// Returns `true` if the rule applies to every path under a prefix, such as `/assets/*`.
+    pub fn is_pattern(&self) -> bool {
+        pattern_prefix(&self.path).is_some()
+    }
+}
+
+/// A function that renders a list of HTTP headers, given the header rules of the site sorted by path.
+pub type HeaderListRenderer = Box<dyn FnOnce(Vec<HeaderRule>) -> String>;
+
+/// Configuration for generating a file listing the HTTP headers of the site's paths.
+pub struct HeaderList {
+    /// The name of the output file.
+    pub file_name: &'static str,
+    /// Function that takes the header rules of the site and returns the header list's content.
+    pub content_renderer: HeaderListRenderer,
+    /// Filter selecting the header rules included in the list.
+    pub filter: RouteFilter,
+}
+
+impl HeaderList {
+    /// Creates a `HeaderList` configuration for the `_headers` file of [Cloudflare Pages](https://pages.cloudflare.com/)
+    /// and [Netlify](https://www.netlify.com/).
+    ///
+    /// The generated file will be named `_headers` and contain a block of indented headers for each path or pattern.
+    /// Paths are percent-encoded, like the sources of redirect lists.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use pagebake::headers::HeaderList;
+    /// use pagebake::render::RenderConfig;
+    /// use pagebake::{Router, get};
+    ///
+    /// let router = Router::new()
+    ///     .route("/", get(|| "<h1>Home</h1>".to_owned()))
+    ///     .header("/assets/*", "Cache-Control", "max-age=31536000, immutable");
+    ///
+    /// let config = RenderConfig {
+    ///     header_lists: vec![HeaderList::for_headers_file()],
+    ///     headers: vec![("X-Frame-Options".to_owned(), "DENY".to_owned())],
+    ///     ..Default::default()
+    /// };
+    ///
+    /// let output = router.render_to_map(config).unwrap();
+    /// assert_eq!(
+    ///     output.extra_files["_headers"],
+    ///     "/*\n  X-Frame-Options: DENY\n\n/assets/*\n  Cache-Control: max-age=31536000, immutable"
+    /// );
+    /// ```
+    pub fn for_headers_file() -> Self {
+        HeaderList {
+            file_name: "_headers",
+            content_renderer: Box::new(|rules: Vec<HeaderRule>| {
+                rules
+                    .iter()
+                    .map(|rule| {
+                        let path = escape::url(&rule.path);
+                        let headers = rule
+                            .headers
+                            .iter()
+                            .map(|(name, value)| format!("  {name}: {value}"))
+                            .collect::<Vec<String>>()
+                            .join("\n");
+
+                        format!("{path}\n{headers}")
+                    })
+                    .collect::<Vec<String>>()
+                    .join("\n\n")
+            }),
+            filter: RouteFilter::default(),
+        }
+    }
+}
+
+/// Checks that a header name is a valid HTTP token and that its value fits on a single line.
+pub(crate) fn check_header(name: &str, value: &str) -> Result<(), String> {
+    let is_token_char = |c: char| c.is_ascii_alphanumeric() || "!#$%&'*+-.^_`|~".contains(c);
+
+    if name.is_empty() || !name.chars().all(is_token_char) {
+        return Err(format!("Invalid header name `{name}`"));
+    }
+    if value.contains(['\r', '\n']) {
+        return Err(format!(
+            "Value of header `{name}` must not contain line breaks"
+        ));
+    }
+
+    Ok(())
+}
@@ -0,0 +1,231 @@
+use crate::{
+    csp::CspOutput,
+    diagnostics::Diagnostic,
+    headers::HeaderList,
+    redirects::{pattern_prefix, RedirectList, UrlStyle},
+    render::RenderConfig,
+    Router,
+};
+
+/// Presets for static hosting services, which bundle the redirect lists, fallback page name and
+/// additional files that each host expects.
+///
+/// The rendering configuration of a preset can be adjusted like any other. When rendering, features
+/// of the site that the host cannot serve, such as nested fallback pages on GitHub Pages, are reported
+/// as `Diagnostic::UnsupportedFeature` according to `RenderConfig::unsupported_features`.
+///
+/// # Examples
+///
+/// ```rust
+/// use pagebake::diagnostics::Diagnostic;
+/// use pagebake::hosts::HostProfile;
+/// use pagebake::{Router, get};
+///
+/// let blog_router = Router::new()
+///     .route("/", get(|| "<h1>Blog</h1>".to_owned()))
+///     .fallback(|| "<h1>Post not found</h1>".to_owned());
+///
+/// let router = Router::new()
+///     .route("/", get(|| "<h1>Home</h1>".to_owned()))
+///     .nest("/blog", blog_router);
+///
+/// let host = HostProfile::GitHubPages {
+///     cname: Some("example.com".to_owned()),
+/// };
+///
+/// let output = router.render_to_map(host.render_config()).unwrap();
+/// assert_eq!(output.extra_files["CNAME"], "example.com\n");
+/// assert_eq!(
+///     output.warnings,
+///     vec![Diagnostic::UnsupportedFeature {
+///         host: "GitHub Pages".to_owned(),
+///         feature: "fallback pages for `/blog/`".to_owned(),
+///     }]
+/// );
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HostProfile {
+    /// [Cloudflare Pages](https://pages.cloudflare.com/), with `_redirects` and `_headers` files.
+    /// Fallback pages are served for their prefix by the host.
+    CloudflarePages,
+    /// [Netlify](https://www.netlify.com/), with `_redirects` and `_headers` files.
+    /// Redirect pages are disabled, since Netlify does not apply redirects to paths that have a file.
+    Netlify,
+    /// [GitHub Pages](https://pages.github.com/), which only supports a fallback page at the root of the site,
+    /// and redirects through redirect pages.
+    /// A `.nojekyll` file is generated so that files starting with `_` are published,
+    /// along with a `CNAME` file for the custom domain, if any.
+    GitHubPages {
+        /// The custom domain of the site, such as `example.com`.
+        cname: Option<String>,
+    },
+    /// [Static Web Server](https://static-web-server.net/), with a `config.toml` file.
+    /// Only a fallback page at the root of the site is served, as its `404.html` page.
+    StaticWebServer,
+    /// [nginx](https://nginx.org/), with an `nginx.conf` fragment to be included in a `server` block.
+    /// Headers are not supported.
+    Nginx {
+        /// How a trailing `/` in the requested path of a page is handled.
+        url_style: UrlStyle,
+    },
+}
+
+impl HostProfile {
+    /// Returns the name of the host, as used in diagnostics.
+    pub fn name(&self) -> &'static str {
+        match self {
+            HostProfile::CloudflarePages => "Cloudflare Pages",
+            HostProfile::Netlify => "Netlify",
+            HostProfile::GitHubPages { .. } => "GitHub Pages",
+            HostProfile::StaticWebServer => "Static Web Server",
+            HostProfile::Nginx { .. } => "nginx",
+        }
+    }
+
+    /// Returns the rendering configuration for the host.
+    pub fn render_config(&self) -> RenderConfig {
+        let mut config = RenderConfig {
+            host: Some(self.clone()),
+            ..Default::default()
+        };
+
+        match self {
+            HostProfile::CloudflarePages => {
+                config.redirect_lists = vec![RedirectList::for_cloudflare_pages()];
+                config.header_lists = vec![HeaderList::for_headers_file()];
+            }
+            HostProfile::Netlify => {
+                config.redirect_page_renderer = None;
+                config.redirect_lists = vec![RedirectList::for_netlify()];
+                config.header_lists = vec![HeaderList::for_headers_file()];
+            }
+            HostProfile::GitHubPages { .. } => {}
+            HostProfile::StaticWebServer => {
+                config.redirect_lists = vec![RedirectList::for_static_web_server()];
+            }
+            HostProfile::Nginx { url_style } => {
+                config.redirect_lists = vec![RedirectList::for_nginx(*url_style)];
+            }
+        }
+
+        config
+    }
+
+    /// Returns the names and contents of the additional files that the host expects in the output.
+    pub(crate) fn files(&self) -> Vec<(&'static str, String)> {
+        match self {
+            HostProfile::GitHubPages { cname } => {
+                let mut files = vec![(".nojekyll", String::new())];
+                if let Some(cname) = cname {
+                    files.push(("CNAME", format!("{cname}\n")));
+                }
+                files
+            }
+            _ => vec![],
+        }
+    }
+
+    /// Returns `true` if the host serves fallback pages for prefixes other than the root of the site.
+    fn supports_nested_fallbacks(&self) -> bool {
+        !matches!(
+            self,
+            HostProfile::GitHubPages { .. } | HostProfile::StaticWebServer
+        )
+    }
+
+    /// Returns `true` if the host only finds fallback pages named `404`.
+    fn requires_404_fallbacks(&self) -> bool {
+        matches!(
+            self,
+            HostProfile::CloudflarePages
+                | HostProfile::GitHubPages { .. }
+                | HostProfile::StaticWebServer
+        )
+    }
+
+    /// Returns `true` if the host can send HTTP headers.
+    pub(crate) fn supports_headers(&self) -> bool {
+        !matches!(
+            self,
+            HostProfile::GitHubPages { .. } | HostProfile::Nginx { .. }
+        )
+    }
+
+    /// Returns `true` if the host can send HTTP redirects, rather than only serving redirect pages.
+    fn supports_redirects(&self) -> bool {
+        !matches!(self, HostProfile::GitHubPages { .. })
+    }
+}
+
+impl Router {
+    /// Finds the features of the site that the host of `config`, if any, cannot serve.
+    pub(crate) fn unsupported_features(&self, config: &RenderConfig) -> Vec<Diagnostic> {
+        let Some(host) = &config.host else {
+            return vec![];
+        };
+
+        let mut features = vec![];
+
+        if !host.supports_nested_fallbacks() {
+            let mut nested_fallbacks: Vec<&String> = self
+                .fallbacks
+                .keys()
+                .filter(|prefix| *prefix != "/")
+                .collect();
+            nested_fallbacks.sort();
+            features.extend(
+                nested_fallbacks
+                    .iter()
+                    .map(|prefix| format!("fallback pages for `{prefix}`")),
+            );
+        }
+
+        if host.requires_404_fallbacks()
+            && !self.fallbacks.is_empty()
+            && config.fallback_page_name != "404"
+        {
+            features.push(format!(
+                "fallback pages named `{}` rather than `404`",
+                config.fallback_page_name
+            ));
+        }
+
+        let csp_header = config
+            .content_security_policy
+            .as_ref()
+            .is_some_and(|policy| policy.output == CspOutput::Header);
+        if !host.supports_headers()
+            && (!self.headers.is_empty() || !config.headers.is_empty() || csp_header)
+        {
+            features.push("HTTP headers".to_owned());
+        }
+
+        if !host.supports_redirects() {
+            let mut redirects: Vec<&String> = self.redirects.keys().collect();
+            redirects.sort();
+
+            if config.redirect_page_renderer.is_none() {
+                features.extend(
+                    redirects
+                        .iter()
+                        .map(|source| format!("redirect `{source}`")),
+                );
+            } else {
+                features.extend(
+                    redirects
+                        .iter()
+                        .filter(|source| pattern_prefix(source).is_some())
+                        .map(|source| format!("pattern redirect `{source}`")),
+                );
+            }
+        }
+
+        features
+            .into_iter()
+            .map(|feature| Diagnostic::UnsupportedFeature {
+                host: host.name().to_owned(),
+                feature,
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,140 @@
+/// A tag found in an HTML document.
+pub(crate) struct Tag {
+    /// The lowercase name of the element, or `!--` for a comment.
+    pub(crate) name: String,
+    /// Whether the tag closes its element, as in `</p>`.
+    pub(crate) closing: bool,
+    /// The lowercase text between the name and the end of the tag.
+    pub(crate) attributes: String,
+    /// The position of the `<` starting the tag.
+    pub(crate) start: usize,
+    /// The position right after the `>` ending the tag.
+    pub(crate) end: usize,
+}
+
+/// Scans the tags and comments of an HTML document in the order they appear.
+///
+/// Tags are matched case-insensitively, and the content of elements such as `<script>`
+/// can be skipped with `skip_to_closing` so that it is not read as tags.
+pub(crate) struct Scanner<'a> {
+    html: &'a str,
+    // ASCII lowercasing keeps byte offsets, so tags can be matched case-insensitively.
+    lowercase: String,
+    position: usize,
+}
+
+impl<'a> Scanner<'a> {
+    /// Creates a scanner starting at the beginning of `html`.
+    pub(crate) fn new(html: &'a str) -> Self {
+        Scanner {
+            html,
+            lowercase: html.to_ascii_lowercase(),
+            position: 0,
+        }
+    }
+
+    /// Returns the position right after the last tag that was scanned or skipped.
+    pub(crate) fn position(&self) -> usize {
+        self.position
+    }
+
+    /// Returns the next tag or comment.
+    ///
+    /// When a tag is not closed, the rest of the document is skipped and `None` is returned.
+    pub(crate) fn next_tag(&mut self) -> Option<Tag> {
+        let start = self.position + self.html[self.position..].find('<')?;
+
+        if self.lowercase[start..].starts_with("<!--") {
+            let end = self.lowercase[start..]
+                .find("-->")
+                .map_or(self.html.len(), |i| start + i + 3);
+            self.position = end;
+            return Some(Tag {
+                name: "!--".to_owned(),
+                closing: false,
+                attributes: String::new(),
+                start,
+                end,
+            });
+        }
+
+        let Some(end) = self.html[start..].find('>').map(|i| start + i + 1) else {
+            self.position = self.html.len();
+            return None;
+        };
+        self.position = end;
+
+        let tag = &self.lowercase[start + 1..end - 1];
+        let closing = tag.starts_with('/');
+        let tag = tag.trim_start_matches('/');
+        let name_end = tag
+            .find(|c: char| c.is_ascii_whitespace() || c == '/')
+            .unwrap_or(tag.len());
+
+        Some(Tag {
+            name: tag[..name_end].to_owned(),
+            closing,
+            attributes: tag[name_end..].to_owned(),
+            start,
+            end,
+        })
+    }
+
+    /// Returns the position of the closing tag of the element `name` after the current position,
+    /// or the end of the document if there is none.
+    pub(crate) fn find_closing(&self, name: &str) -> usize {
+        self.lowercase[self.position..]
+            .find(&format!("</{name}"))
+            .map_or(self.html.len(), |i| self.position + i)
+    }
+
+    /// Skips the content of the element `name`, up to its closing tag, and returns the position of that tag.
+    pub(crate) fn skip_to_closing(&mut self, name: &str) -> usize {
+        self.position = self.find_closing(name);
+        self.position
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scans_tags_and_comments() {
+        let html = "<P class=\"a\">x<!-- <b> --></p><br/>";
+        let mut scanner = Scanner::new(html);
+
+        let tags: Vec<(String, bool, String)> = std::iter::from_fn(|| scanner.next_tag())
+            .map(|tag| (tag.name, tag.closing, tag.attributes))
+            .collect();
+        assert_eq!(
+            tags,
+            vec![
+                ("p".to_owned(), false, " class=\"a\"".to_owned()),
+                ("!--".to_owned(), false, String::new()),
+                ("p".to_owned(), true, String::new()),
+                ("br".to_owned(), false, "/".to_owned()),
+            ]
+        );
+    }
+
+    #[test]
+    fn skips_element_content() {
+        let html = "<script>if (a < b) {}</SCRIPT><p>";
+        let mut scanner = Scanner::new(html);
+
+        let script = scanner.next_tag().unwrap();
+        let content_end = scanner.skip_to_closing(&script.name);
+        assert_eq!(&html[script.end..content_end], "if (a < b) {}");
+        assert!(scanner.next_tag().unwrap().closing);
+        assert_eq!(scanner.next_tag().unwrap().name, "p");
+        assert!(scanner.next_tag().is_none());
+    }
+
+    #[test]
+    fn skips_unclosed_tags() {
+        let mut scanner = Scanner::new("text <p");
+        assert!(scanner.next_tag().is_none());
+        assert_eq!(scanner.position(), "text <p".len());
+    }
+}
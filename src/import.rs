@@ -0,0 +1,699 @@
+use std::{collections::HashMap, fmt};
+
+use crate::{
+    escape,
+    redirects::{check_redirect, Redirect, SPLAT, WILDCARD},
+    Response, Router,
+};
+
+/// A malformed entry found while parsing a redirect file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError {
+    /// The 1-based line number the entry starts on.
+    pub line: usize,
+    /// Description of the problem.
+    pub message: String,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+/// Collects parsed redirects, decoding and validating them and reporting duplicate sources.
+#[derive(Default)]
+struct Parsed {
+    redirects: Vec<Redirect>,
+    lines: HashMap<String, usize>,
+    errors: Vec<ParseError>,
+}
+
+impl Parsed {
+    fn error(&mut self, line: usize, message: impl Into<String>) {
+        self.errors.push(ParseError {
+            line,
+            message: message.into(),
+        });
+    }
+
+    fn push(
+        &mut self,
+        line: usize,
+        source: String,
+        target: String,
+        status: u16,
+        preserve_query: bool,
+    ) {
+        let (source, target) = (escape::decode_url(&source), escape::decode_url(&target));
+
+        if let Err(message) = check_redirect(&source, &target, status) {
+            return self.error(line, message);
+        }
+
+        if let Some(first) = self.lines.get(&source) {
+            let message =
+                format!("Duplicate redirect for `{source}`, first defined on line {first}");
+            return self.error(line, message);
+        }
+
+        self.lines.insert(source.clone(), line);
+        self.redirects.push(Redirect {
+            source,
+            target,
+            status,
+            preserve_query,
+        });
+    }
+
+    fn finish(self) -> Result<Vec<Redirect>, Vec<ParseError>> {
+        if self.errors.is_empty() {
+            Ok(self.redirects)
+        } else {
+            Err(self.errors)
+        }
+    }
+}
+
+/// Parses a [Cloudflare Pages](https://pages.cloudflare.com/) `_redirects` file.
+///
+/// Each line contains a source, a target and an optional status code, separated by whitespace.
+/// Empty lines and lines starting with `#` are ignored. Splats are supported, named placeholders are not.
+/// Percent-encoded sources and targets are decoded, so that redirects written by
+/// `RedirectList::for_cloudflare_pages` are read back as they were defined.
+///
+/// # Errors
+///
+/// Returns every malformed line along with its line number.
+///
+/// # Examples
+///
+/// ```rust
+/// use pagebake::import;
+///
+/// let redirects = import::parse_cloudflare_pages("# Moved pages\n/old /new 301\n/docs/v1/* /docs/:splat\n").unwrap();
+/// assert_eq!(redirects[0].status, 301);
+/// assert_eq!(redirects[1].status, 302);
+/// ```
+///
+/// Redirects written to a `_redirects` file are read back unchanged:
+///
+/// ```rust
+/// use pagebake::import;
+/// use pagebake::redirects::RedirectList;
+/// use pagebake::render::RenderConfig;
+/// use pagebake::{Router, get, permanent_redirect, redirect};
+///
+/// let router = Router::new()
+///     .route("/new page", get(|| "<h1>New</h1>".to_owned()))
+///     .route("/my page", permanent_redirect("/new page"))
+///     .route("/docs/v1/*", redirect("/docs/:splat"));
+///
+/// let config = RenderConfig {
+///     redirect_lists: vec![RedirectList::for_cloudflare_pages()],
+///     ..Default::default()
+/// };
+/// let output = router.render_to_map(config).unwrap();
+///
+/// let redirects = import::parse_cloudflare_pages(&output.extra_files["_redirects"]).unwrap();
+/// let redirects: Vec<_> = redirects
+///     .iter()
+///     .map(|r| (r.source.as_str(), r.target.as_str(), r.status))
+///     .collect();
+/// assert_eq!(
+///     redirects,
+///     vec![("/my page", "/new page", 301), ("/docs/v1/*", "/docs/:splat", 302)]
+/// );
+/// ```
+pub fn parse_cloudflare_pages(content: &str) -> Result<Vec<Redirect>, Vec<ParseError>> {
+    let mut parsed = Parsed::default();
+
+    for (i, line) in content.lines().enumerate() {
+        let line_number = i + 1;
+        let line = line.trim();
+
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        if !(2..=3).contains(&fields.len()) {
+            parsed.error(line_number, "Expected `source target [status]`");
+            continue;
+        }
+
+        let status = match fields.get(2).map(|s| s.parse::<u16>()) {
+            None => 302,
+            Some(Ok(status)) => status,
+            Some(Err(_)) => {
+                parsed.error(line_number, format!("Invalid status code `{}`", fields[2]));
+                continue;
+            }
+        };
+
+        if fields[0].split('/').any(|segment| segment.starts_with(':')) {
+            parsed.error(line_number, "Named placeholders are not supported");
+            continue;
+        }
+
+        parsed.push(
+            line_number,
+            fields[0].to_owned(),
+            fields[1].to_owned(),
+            status,
+            false,
+        );
+    }
+
+    parsed.finish()
+}
+
+/// Parses the redirects of a [Static Web Server](https://static-web-server.net/) `config.toml` file.
+///
+/// Only the `[[advanced.redirects]]` tables are read, and all other settings are ignored.
+/// Sources ending in `/**` become pattern redirects, with a `$1` at the end of the destination
+/// standing for the splat. Percent-encoded sources and destinations are decoded, so that redirects
+/// written by `RedirectList::for_static_web_server` are read back as they were defined.
+///
+/// # Errors
+///
+/// Returns every malformed entry along with the line number it starts on.
+///
+/// # Examples
+///
+/// ```rust
+/// use pagebake::import;
+///
+/// let config = r#"
+/// [[advanced.redirects]]
+/// source = "/old/**"
+/// destination = "/new/$1"
+/// kind = 301
+/// "#;
+///
+/// let redirects = import::parse_static_web_server(config).unwrap();
+/// assert_eq!(redirects[0].source, "/old/*");
+/// assert_eq!(redirects[0].target, "/new/:splat");
+/// ```
+///
+/// Redirects written to a `config.toml` file are read back unchanged:
+///
+/// ```rust
+/// use pagebake::import;
+/// use pagebake::redirects::RedirectList;
+/// use pagebake::render::RenderConfig;
+/// use pagebake::{Router, get, permanent_redirect, redirect};
+///
+/// let router = Router::new()
+///     .route("/new page", get(|| "<h1>New</h1>".to_owned()))
+///     .route("/my page", permanent_redirect("/new page"))
+///     .route("/old docs/*", redirect("/docs/:splat"));
+///
+/// let config = RenderConfig {
+///     redirect_lists: vec![RedirectList::for_static_web_server()],
+///     ..Default::default()
+/// };
+/// let output = router.render_to_map(config).unwrap();
+///
+/// let redirects = import::parse_static_web_server(&output.extra_files["config.toml"]).unwrap();
+/// let redirects: Vec<_> = redirects
+///     .iter()
+///     .map(|r| (r.source.as_str(), r.target.as_str(), r.status))
+///     .collect();
+/// assert_eq!(
+///     redirects,
+///     vec![("/my page", "/new page", 301), ("/old docs/*", "/docs/:splat", 302)]
+/// );
+/// ```
+pub fn parse_static_web_server(content: &str) -> Result<Vec<Redirect>, Vec<ParseError>> {
+    /// A `[[advanced.redirects]]` table and the line its header is on.
+    struct Entry {
+        line: usize,
+        source: Option<String>,
+        destination: Option<String>,
+        kind: Option<u16>,
+    }
+
+    fn finish_entry(parsed: &mut Parsed, entry: Entry) {
+        let (Some(source), Some(destination), Some(kind)) =
+            (entry.source, entry.destination, entry.kind)
+        else {
+            return parsed.error(
+                entry.line,
+                "Redirect must have a `source`, a `destination` and a `kind`",
+            );
+        };
+
+        match source.strip_suffix("/**") {
+            Some(prefix) => {
+                let target = match destination.strip_suffix("$1") {
+                    Some(target_prefix) => format!("{target_prefix}{SPLAT}"),
+                    None => destination,
+                };
+                parsed.push(
+                    entry.line,
+                    format!("{prefix}{WILDCARD}"),
+                    target,
+                    kind,
+                    false,
+                );
+            }
+            None => parsed.push(entry.line, source, destination, kind, false),
+        }
+    }
+
+    let mut parsed = Parsed::default();
+    let mut entry: Option<Entry> = None;
+
+    for (i, line) in content.lines().enumerate() {
+        let line_number = i + 1;
+        let line = line.trim();
+
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        if line.starts_with('[') {
+            if let Some(entry) = entry.take() {
+                finish_entry(&mut parsed, entry);
+            }
+
+            let header: String = line.split('#').next().unwrap().split_whitespace().collect();
+            if header == "[[advanced.redirects]]" {
+                entry = Some(Entry {
+                    line: line_number,
+                    source: None,
+                    destination: None,
+                    kind: None,
+                });
+            }
+            continue;
+        }
+
+        let Some(entry) = entry.as_mut() else {
+            continue;
+        };
+
+        let Some((key, value)) = line.split_once('=') else {
+            parsed.error(line_number, "Expected `key = value`");
+            continue;
+        };
+
+        let result = match key.trim() {
+            "source" => parse_toml_string(value).map(|v| entry.source = Some(v)),
+            "destination" => parse_toml_string(value).map(|v| entry.destination = Some(v)),
+            "kind" => parse_toml_integer(value).map(|v| entry.kind = Some(v)),
+            _ => Ok(()),
+        };
+
+        if let Err(message) = result {
+            parsed.error(line_number, message);
+        }
+    }
+
+    if let Some(entry) = entry.take() {
+        finish_entry(&mut parsed, entry);
+    }
+
+    parsed.finish()
+}
+
+/// Parses a CSV file of redirects, such as the one generated by `RedirectList::for_csv`.
+///
+/// Each record contains a source, a target, an optional status code and an optional `true` or `false`
+/// for `Redirect::preserve_query`. A first record naming these columns, as in `source,target,status`,
+/// is treated as a header and skipped.
+/// Fields may be quoted as described in [RFC 4180](https://www.rfc-editor.org/rfc/rfc4180).
+/// Percent-encoded sources and targets are decoded, so that redirects written by
+/// `RedirectList::for_csv` are read back as they were defined.
+///
+/// # Errors
+///
+/// Returns every malformed record along with the line number it starts on.
+///
+/// # Examples
+///
+/// ```rust
+/// use pagebake::import;
+///
+/// let redirects = import::parse_csv("source,target,status\n/old,/new,308\n").unwrap();
+/// assert_eq!(redirects[0].target, "/new");
+/// ```
+///
+/// Redirects written to a CSV file are read back unchanged:
+///
+/// ```rust
+/// use pagebake::import;
+/// use pagebake::redirects::RedirectList;
+/// use pagebake::render::RenderConfig;
+/// use pagebake::{Router, get, permanent_redirect, redirect};
+///
+/// let router = Router::new()
+///     .route("/new page", get(|| "<h1>New</h1>".to_owned()))
+///     .route("/my page", permanent_redirect("/new page"))
+///     .route("/a,b", redirect("https://example.com/?q=\"a\""))
+///     .route("/docs/v1/*", redirect("/docs/:splat"));
+///
+/// let config = RenderConfig {
+///     redirect_lists: vec![RedirectList::for_csv()],
+///     preserve_query: true,
+///     ..Default::default()
+/// };
+/// let output = router.render_to_map(config).unwrap();
+///
+/// let redirects = import::parse_csv(&output.extra_files["redirects.csv"]).unwrap();
+/// let redirects: Vec<_> = redirects
+///     .iter()
+///     .map(|r| (r.source.as_str(), r.target.as_str(), r.status, r.preserve_query))
+///     .collect();
+/// assert_eq!(
+///     redirects,
+///     vec![
+///         ("/a,b", "https://example.com/?q=\"a\"", 302, true),
+///         ("/my page", "/new page", 301, true),
+///         ("/docs/v1/*", "/docs/:splat", 302, true),
+///     ]
+/// );
+/// ```
+pub fn parse_csv(content: &str) -> Result<Vec<Redirect>, Vec<ParseError>> {
+    let mut parsed = Parsed::default();
+
+    for (index, record) in csv_records(content).into_iter().enumerate() {
+        let (line_number, fields) = match record {
+            Ok(record) => record,
+            Err(error) => {
+                parsed.errors.push(error);
+                continue;
+            }
+        };
+
+        if fields.len() == 1 && fields[0].trim().is_empty() {
+            continue;
+        }
+
+        let is_header = fields.iter().map(|f| f.trim().to_ascii_lowercase()).eq([
+            "source",
+            "target",
+            "status",
+            "preserve_query",
+        ]
+        .into_iter()
+        .take(fields.len()));
+        if index == 0 && is_header {
+            continue;
+        }
+
+        if !(2..=4).contains(&fields.len()) {
+            parsed.error(
+                line_number,
+                "Expected `source,target[,status[,preserve_query]]`",
+            );
+            continue;
+        }
+
+        let status = match fields.get(2).map(|s| s.trim()) {
+            None | Some("") => 302,
+            Some(status) => match status.parse::<u16>() {
+                Ok(status) => status,
+                Err(_) => {
+                    parsed.error(line_number, format!("Invalid status code `{status}`"));
+                    continue;
+                }
+            },
+        };
+
+        let preserve_query = match fields.get(3).map(|s| s.trim()) {
+            None | Some("") | Some("false") => false,
+            Some("true") => true,
+            Some(value) => {
+                parsed.error(
+                    line_number,
+                    format!("Expected `true` or `false` for `preserve_query`. Found `{value}`"),
+                );
+                continue;
+            }
+        };
+
+        parsed.push(
+            line_number,
+            fields[0].trim().to_owned(),
+            fields[1].trim().to_owned(),
+            status,
+            preserve_query,
+        );
+    }
+
+    parsed.finish()
+}
+
+/// Splits CSV content into records, each paired with the line number it starts on.
+fn csv_records(content: &str) -> Vec<Result<(usize, Vec<String>), ParseError>> {
+    let mut records = Vec::new();
+    let mut chars = content.chars().peekable();
+    let mut line = 1;
+
+    while chars.peek().is_some() {
+        let start = line;
+        let mut fields = vec![String::new()];
+        let mut quoted = false;
+
+        while let Some(c) = chars.next() {
+            match c {
+                '"' if quoted && chars.peek() == Some(&'"') => {
+                    chars.next();
+                    fields.last_mut().unwrap().push('"');
+                }
+                '"' if quoted => quoted = false,
+                '"' if fields.last().unwrap().is_empty() => quoted = true,
+                ',' if !quoted => fields.push(String::new()),
+                '\r' if !quoted && chars.peek() == Some(&'\n') => {}
+                '\n' if !quoted => {
+                    line += 1;
+                    break;
+                }
+                c => {
+                    if c == '\n' {
+                        line += 1;
+                    }
+                    fields.last_mut().unwrap().push(c);
+                }
+            }
+        }
+
+        records.push(if quoted {
+            Err(ParseError {
+                line: start,
+                message: "Unterminated quoted field".to_owned(),
+            })
+        } else {
+            Ok((start, fields))
+        });
+    }
+
+    records
+}
+
+/// Parses a TOML basic or literal string value, ignoring a trailing comment.
+fn parse_toml_string(value: &str) -> Result<String, String> {
+    let value = value.trim();
+    let mut chars = value.chars();
+    let mut parsed = String::new();
+
+    let rest = match chars.next() {
+        Some('\'') => {
+            let end = value[1..]
+                .find('\'')
+                .ok_or("Unterminated string".to_owned())?;
+            parsed.push_str(&value[1..end + 1]);
+            &value[end + 2..]
+        }
+        Some('"') => loop {
+            match chars.next() {
+                Some('"') => break chars.as_str(),
+                Some('\\') => match chars.next() {
+                    Some('"') => parsed.push('"'),
+                    Some('\\') => parsed.push('\\'),
+                    Some('n') => parsed.push('\n'),
+                    Some('r') => parsed.push('\r'),
+                    Some('t') => parsed.push('\t'),
+                    Some('b') => parsed.push('\u{8}'),
+                    Some('f') => parsed.push('\u{c}'),
+                    Some(u @ ('u' | 'U')) => {
+                        let len = if u == 'u' { 4 } else { 8 };
+                        let hex: String = chars.by_ref().take(len).collect();
+                        let c = u32::from_str_radix(&hex, 16)
+                            .ok()
+                            .and_then(char::from_u32)
+                            .ok_or(format!("Invalid unicode escape `\\{u}{hex}`"))?;
+                        parsed.push(c);
+                    }
+                    _ => return Err("Invalid escape sequence".to_owned()),
+                },
+                Some(c) => parsed.push(c),
+                None => return Err("Unterminated string".to_owned()),
+            }
+        },
+        _ => return Err(format!("Expected a string. Found `{value}`")),
+    };
+
+    check_toml_rest(rest)?;
+    Ok(parsed)
+}
+
+/// Parses a TOML integer value that fits a status code, ignoring a trailing comment.
+fn parse_toml_integer(value: &str) -> Result<u16, String> {
+    let value = value.trim();
+    let end = value
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(value.len());
+
+    check_toml_rest(&value[end..])?;
+    value[..end]
+        .parse()
+        .map_err(|_| format!("Expected a status code. Found `{value}`"))
+}
+
+/// Checks that nothing but whitespace or a comment follows a TOML value.
+fn check_toml_rest(rest: &str) -> Result<(), String> {
+    let rest = rest.trim();
+
+    if rest.is_empty() || rest.starts_with('#') {
+        Ok(())
+    } else {
+        Err(format!("Unexpected `{rest}` after value"))
+    }
+}
+
+impl Router {
+    /// Adds a list of redirects, such as ones parsed from an existing redirect file, to the `Router`.
+    /// The `preserve_query` flag of each redirect is kept.
+    ///
+    /// # Panics
+    ///
+    /// Panics under the same conditions as [`Router::route`].
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use pagebake::{get, import, Router};
+    ///
+    /// let redirects = import::parse_cloudflare_pages("/old-home / 301").unwrap();
+    ///
+    /// let router = Router::new()
+    ///     .route("/", get(|| "<h1>Home</h1>".to_owned()))
+    ///     .import_redirects(redirects);
+    /// ```
+    pub fn import_redirects(self, redirects: Vec<Redirect>) -> Self {
+        redirects.into_iter().fold(self, |router, redirect| {
+            let mut router = router.route(
+                &redirect.source,
+                Response::Redirect(redirect.target, redirect.status),
+            );
+            if let Some(imported) = router.redirects.get_mut(&redirect.source) {
+                imported.preserve_query = redirect.preserve_query;
+            }
+            router
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(line: usize, fields: &[&str]) -> Result<(usize, Vec<String>), ParseError> {
+        Ok((line, fields.iter().map(|f| f.to_string()).collect()))
+    }
+
+    #[test]
+    fn csv_records_split_lines_and_fields() {
+        assert_eq!(
+            csv_records("source,target\r\n/a,/b\n/c,,\n"),
+            vec![
+                record(1, &["source", "target"]),
+                record(2, &["/a", "/b"]),
+                record(3, &["/c", "", ""]),
+            ]
+        );
+    }
+
+    #[test]
+    fn csv_records_read_quoted_fields() {
+        assert_eq!(
+            csv_records("\"/a,b\",\"say \"\"hi\"\"\",301\n/a\"b,/c"),
+            vec![
+                record(1, &["/a,b", "say \"hi\"", "301"]),
+                // Quotes are only special at the start of a field.
+                record(2, &["/a\"b", "/c"]),
+            ]
+        );
+        assert_eq!(
+            csv_records("\"/a\r\nb\",/c\n/d,/e"),
+            vec![record(1, &["/a\r\nb", "/c"]), record(3, &["/d", "/e"])]
+        );
+    }
+
+    #[test]
+    fn csv_records_report_unterminated_quotes() {
+        assert_eq!(
+            csv_records("/a,/b\n\"/c,/d\n/e,/f\n"),
+            vec![
+                record(1, &["/a", "/b"]),
+                Err(ParseError {
+                    line: 2,
+                    message: "Unterminated quoted field".to_owned(),
+                }),
+            ]
+        );
+    }
+
+    #[test]
+    fn toml_strings_are_unescaped() {
+        assert_eq!(
+            parse_toml_string(r#" "/a\"b\\c\t\u00e9\U0001F600" # comment"#),
+            Ok("/a\"b\\c\té😀".to_owned())
+        );
+        assert_eq!(
+            parse_toml_string(r"'C:\path\n'"),
+            Ok(r"C:\path\n".to_owned())
+        );
+        assert_eq!(parse_toml_string(r#""""#), Ok(String::new()));
+    }
+
+    #[test]
+    fn malformed_toml_strings_are_rejected() {
+        let errors = [
+            (r#""/a"#, "Unterminated string"),
+            ("'/a", "Unterminated string"),
+            (r#""\x""#, "Invalid escape sequence"),
+            (r#""\uD800""#, r"Invalid unicode escape `\uD800`"),
+            (r#""\u12""#, "Invalid unicode escape `\\u12\"`"),
+            ("/a", "Expected a string. Found `/a`"),
+            (r#""/a" "/b""#, r#"Unexpected `"/b"` after value"#),
+        ];
+        for (value, message) in errors {
+            assert_eq!(parse_toml_string(value), Err(message.to_owned()), "{value}");
+        }
+    }
+
+    #[test]
+    fn toml_integers_must_be_status_codes() {
+        assert_eq!(parse_toml_integer(" 301 # permanent"), Ok(301));
+        assert_eq!(
+            parse_toml_integer("301x"),
+            Err("Unexpected `x` after value".to_owned())
+        );
+        assert_eq!(
+            parse_toml_integer("-1"),
+            Err("Unexpected `-1` after value".to_owned())
+        );
+        assert_eq!(
+            parse_toml_integer("70000"),
+            Err("Expected a status code. Found `70000`".to_owned())
+        );
+        assert_eq!(
+            parse_toml_integer(""),
+            Err("Expected a status code. Found ``".to_owned())
+        );
+    }
+}
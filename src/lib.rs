@@ -1,34 +1,156 @@
-use std::collections::HashMap;
+use std::collections::{BTreeSet, HashMap, HashSet};
 
+use diagnostics::Origin;
+use headers::check_header;
+use redirects::{
+    check_path, check_redirect, is_external_url, pattern_prefix, Redirect, SPLAT, WILDCARD,
+};
+use routes::{check_meta, RouteMeta};
+
+mod conflicts;
+pub mod csp;
+pub mod diagnostics;
+mod escape;
+pub mod feeds;
+pub mod headers;
+pub mod hosts;
+mod html;
+pub mod import;
+pub mod manifest;
 pub mod redirects;
 pub mod render;
+pub mod robots;
 pub mod routes;
+pub mod search;
 
 /// Router type to map paths to pages.
 pub struct Router {
     routes: HashMap<String, Box<dyn FnOnce() -> String>>,
-    redirects: HashMap<String, String>,
+    /// Metadata of the registered pages, keyed by route path.
+    route_meta: HashMap<String, RouteMeta>,
+    redirects: HashMap<String, Redirect>,
+    /// Sources of redirects whose target is not prefixed when nesting.
+    site_absolute_redirects: HashSet<String>,
+    /// Sources of redirects that were nested under a prefix, whose relative targets can be resolved.
+    nested_redirects: HashSet<String>,
     fallbacks: HashMap<String, Box<dyn FnOnce() -> String>>,
+    /// Overlapping entries found while merging routers, which are reported when rendering.
+    overlaps: Vec<(Origin, Origin)>,
+    /// Prefixes that routers were nested under.
+    sections: HashSet<String>,
+    /// Tags of pages, redirects and fallbacks.
+    tags: HashMap<Origin, BTreeSet<String>>,
+    /// Tags of the router itself, which apply to all of its entries.
+    router_tags: BTreeSet<String>,
+    /// HTTP headers, keyed by the path or pattern they are sent for.
+    headers: HashMap<String, Vec<(String, String)>>,
 }
 
 /// Possible responses that route paths can be mapped to.
 pub enum Response {
-    /// GET response wrapping the provided page rendering function.
+    /// GET response wrapping the provided page rendering function, along with the page's metadata.
     ///
     /// # Examples
     ///
     /// ```rust
-    /// Response::Get(Box::new(|| "<h1>Hello, world!</h1>".to_owned()));
+    /// use pagebake::Response;
+    ///
+    /// Response::Get {
+    ///     page: Box::new(|| "<h1>Hello, world!</h1>".to_owned()),
+    ///     meta: Default::default(),
+    /// };
+    /// ```
+    Get {
+        page: Box<dyn FnOnce() -> String>,
+        meta: Box<RouteMeta>,
+    },
+    /// Redirect response that points to another path or external URL with the given HTTP status code.
+    ///
+    /// Targets starting with a `/` are prefixed when the router is nested.
+    /// Relative targets, such as `../contact`, are resolved against the final path of the redirect,
+    /// and are only allowed in nested routers.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use pagebake::Response;
+    ///
+    /// Response::Redirect("/home".to_owned(), 308);
     /// ```
-    Get(Box<dyn FnOnce() -> String>),
-    /// Redirect response that points to another path.
+    Redirect(String, u16),
+    /// Redirect response whose target is relative to the root of the site,
+    /// so that it is not prefixed when the router is nested.
     ///
     /// # Examples
     ///
     /// ```rust
-    /// Response::Redirect("/home".to_owned());
+    /// use pagebake::Response;
+    ///
+    /// Response::SiteAbsoluteRedirect("/contact".to_owned(), 302);
     /// ```
-    Redirect(String),
+    SiteAbsoluteRedirect(String, u16),
+}
+
+impl Response {
+    /// Marks a redirect target as relative to the root of the site,
+    /// so that it is not prefixed when the router is nested.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the response is not a redirect.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use pagebake::{Router, redirect};
+    ///
+    /// let blog_router = Router::new().route("/about", redirect("/contact").site_absolute());
+    ///
+    /// // "/blog/about" redirects to "/contact" rather than "/blog/contact".
+    /// let router = Router::new().nest("/blog", blog_router);
+    /// ```
+    pub fn site_absolute(self) -> Self {
+        match self {
+            Response::Redirect(target, status) | Response::SiteAbsoluteRedirect(target, status) => {
+                Response::SiteAbsoluteRedirect(target, status)
+            }
+            Response::Get { .. } => panic!("Only redirect targets can be site-absolute"),
+        }
+    }
+
+    /// Attaches metadata, such as sitemap fields, to a page.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the response is not a page.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use pagebake::routes::{ChangeFreq, RouteMeta};
+    /// use pagebake::{Router, get};
+    ///
+    /// let router = Router::new().route(
+    ///     "/",
+    ///     get(|| "<h1>Home</h1>".to_owned()).with_meta(RouteMeta {
+    ///         lastmod: Some("2024-05-01".to_owned()),
+    ///         changefreq: Some(ChangeFreq::Weekly),
+    ///         priority: Some(1.0),
+    ///         ..Default::default()
+    ///     }),
+    /// );
+    /// ```
+    pub fn with_meta(self, meta: RouteMeta) -> Self {
+        match self {
+            Response::Get { page, .. } => Response::Get {
+                page,
+                meta: Box::new(meta),
+            },
+            Response::Redirect(..) | Response::SiteAbsoluteRedirect(..) => {
+                panic!("Only pages can have metadata")
+            }
+        }
+    }
 }
 
 /// Wraps a page rendering function into a GET response.
@@ -42,18 +164,39 @@ pub fn get<R>(page: R) -> Response
 where
     R: FnOnce() -> String + 'static,
 {
-    Response::Get(Box::new(page))
+    Response::Get {
+        page: Box::new(page),
+        meta: Box::default(),
+    }
 }
 
-/// Creates a redirect response to the specified path.
+/// Creates a temporary (302) redirect response to the specified path or external URL.
 ///
 /// # Examples
 ///
 /// ```rust
 /// pagebake::redirect("/home");
+/// pagebake::redirect("https://github.com/boralg/pagebake");
 /// ```
 pub fn redirect(path: &str) -> Response {
-    Response::Redirect(path.to_owned())
+    Response::Redirect(path.to_owned(), 302)
+}
+
+/// Creates a permanent (301) redirect response to the specified path or external URL.
+///
+/// # Examples
+///
+/// ```rust
+/// pagebake::permanent_redirect("/home");
+/// ```
+pub fn permanent_redirect(path: &str) -> Response {
+    Response::Redirect(path.to_owned(), 301)
+}
+
+impl Default for Router {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl Router {
@@ -61,8 +204,16 @@ impl Router {
     pub fn new() -> Self {
         Self {
             routes: HashMap::new(),
+            route_meta: HashMap::new(),
             redirects: HashMap::new(),
+            site_absolute_redirects: HashSet::new(),
+            nested_redirects: HashSet::new(),
             fallbacks: HashMap::new(),
+            overlaps: Vec::new(),
+            sections: HashSet::new(),
+            tags: HashMap::new(),
+            router_tags: BTreeSet::new(),
+            headers: HashMap::new(),
         }
     }
 
@@ -70,10 +221,19 @@ impl Router {
     ///
     /// Depending on the `response` variant, the route will either render a page or perform a redirect.
     /// The provided `path` must start with a `/` and must not conflict with existing pages or redirects.
+    /// Redirect targets can be paths starting with a `/`, absolute URLs such as `https://example.com/`
+    /// or `mailto:hello@example.com`, and, in routers that are nested, paths relative to the redirect
+    /// such as `../contact`.
+    ///
+    /// A redirect `path` ending in `/*` matches every path under its prefix.
+    /// Its target may end in `:splat`, which is replaced by the part of the path matched by the `*`.
     ///
     /// # Panics
     ///
-    /// Panics if the path is invalid or if a handler for the specified path already exists.
+    /// Panics if the path is invalid, if a wildcard or splat is used anywhere else,
+    /// if a redirect status is not a 3xx code, if a redirect target uses the `javascript:`, `data:`
+    /// or `vbscript:` scheme, if the metadata of a page is invalid,
+    /// or if a handler for the specified path already exists.
     ///
     /// # Examples
     ///
@@ -83,36 +243,96 @@ impl Router {
     /// let router = Router::new()
     ///     .route("/", get(|| "<h1>Home</h1>".to_owned()))
     ///     .route("/about", get(|| "<h1>About</h1>".to_owned()))
-    ///     .route("/old-home", redirect("/"));
+    ///     .route("/old-home", redirect("/"))
+    ///     .route("/source", redirect("https://github.com/boralg/pagebake"))
+    ///     .route("/docs/v1/*", redirect("/docs/legacy/:splat"));
     /// ```
     pub fn route(mut self, path: &str, response: Response) -> Self {
-        fn validate_path(path: &str) {
-            if path.is_empty() {
-                panic!("Paths must start with a `/`. Use \"/\" for root routes");
-            } else if !path.starts_with('/') {
-                panic!("Paths must start with a `/`");
-            }
+        if let Err(message) = check_path(path) {
+            panic!("{message}");
         }
 
-        validate_path(path);
-
         if self.routes.contains_key(path) || self.redirects.contains_key(path) {
             panic!("Overlapping method route. Handler for `{path}` already exists");
         }
 
+        let site_absolute = matches!(response, Response::SiteAbsoluteRedirect(..));
+
         match response {
-            Response::Get(page) => {
+            Response::Get { page, meta } => {
+                if pattern_prefix(path).is_some() {
+                    panic!(
+                        "Wildcard paths can only be redirected. Page for `{path}` is not allowed"
+                    );
+                }
+                if let Err(message) = check_meta(&meta) {
+                    panic!("{message}");
+                }
+                if !meta.headers.is_empty() {
+                    self.headers
+                        .entry(path.to_owned())
+                        .or_default()
+                        .extend(meta.headers.iter().cloned());
+                }
+                if !meta.tags.is_empty() {
+                    self.tags.insert(
+                        Origin::Page(path.to_owned()),
+                        meta.tags.iter().cloned().collect(),
+                    );
+                }
                 self.routes.insert(path.to_owned(), page);
+                self.route_meta.insert(path.to_owned(), *meta);
             }
-            Response::Redirect(redirect_path) => {
-                validate_path(&redirect_path);
-                self.redirects.insert(path.to_owned(), redirect_path);
+            Response::Redirect(redirect_path, status)
+            | Response::SiteAbsoluteRedirect(redirect_path, status) => {
+                if let Err(message) = check_redirect(path, &redirect_path, status) {
+                    panic!("{message}");
+                }
+                if site_absolute {
+                    self.site_absolute_redirects.insert(path.to_owned());
+                }
+                self.redirects.insert(
+                    path.to_owned(),
+                    Redirect {
+                        source: path.to_owned(),
+                        target: redirect_path,
+                        status,
+                        preserve_query: false,
+                    },
+                );
             }
         };
 
         self
     }
 
+    /// Redirects every path under the `from` prefix to the same path under the `to` prefix.
+    ///
+    /// This is a shorthand for a redirect from `{from}/*` to `{to}/:splat`.
+    ///
+    /// # Panics
+    ///
+    /// Panics under the same conditions as [`Router::route`].
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use pagebake::{Router, get};
+    ///
+    /// let router = Router::new()
+    ///     .route("/docs/legacy/intro", get(|| "<h1>Introduction</h1>".to_owned()))
+    ///     .redirect_prefix("/docs/v1", "/docs/legacy");
+    /// ```
+    pub fn redirect_prefix(self, from: &str, to: &str) -> Self {
+        let from = from.trim_end_matches('/');
+        let to = to.trim_end_matches('/');
+
+        self.route(
+            &format!("{from}{WILDCARD}"),
+            redirect(&format!("{to}/{SPLAT}")),
+        )
+    }
+
     /// Sets a fallback handler for unmatched routes.
     ///
     /// The fallback page is used when no other route matches the incoming path.
@@ -142,13 +362,102 @@ impl Router {
         self
     }
 
-    /// Merges another `Router` into the current one.
+    /// Tags every page, redirect and fallback of the `Router`, including the ones added later
+    /// and the ones of routers merged or nested into it.
     ///
-    /// This method combines routes, redirects, and fallback handlers from another router.
+    /// Tags are kept when the router is merged or nested into another one,
+    /// and can be used to filter route and redirect lists.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use pagebake::{Router, get};
+    ///
+    /// let docs_router = Router::new()
+    ///     .route("/", get(|| "<h1>Docs</h1>".to_owned()))
+    ///     .tag("docs");
+    ///
+    /// let router = Router::new().nest("/docs", docs_router);
+    /// ```
+    pub fn tag(mut self, tag: &str) -> Self {
+        self.router_tags.insert(tag.to_owned());
+        self
+    }
+
+    /// Sends an HTTP header for a path of the `Router`, such as `/about`,
+    /// or for every path under a prefix when the path ends in `/*`, such as `/assets/*`.
+    ///
+    /// Paths are prefixed when the router is nested, so `/*` covers every path of a nested router.
+    /// Headers are written to header lists, such as `HeaderList::for_headers_file`, and to redirect lists of hosts
+    /// that read them from the same file.
+    /// Headers of a single page can also be set through its metadata.
     ///
     /// # Panics
     ///
-    /// Panics if there is an overlapping route, redirect, or fallback.
+    /// Panics if the path is invalid, if the header name is not a valid HTTP token,
+    /// or if the value contains line breaks.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use pagebake::{Router, get};
+    ///
+    /// let docs_router = Router::new()
+    ///     .route("/", get(|| "<h1>Docs</h1>".to_owned()))
+    ///     .header("/*", "Cache-Control", "max-age=3600");
+    ///
+    /// let router = Router::new()
+    ///     .header("/*", "X-Frame-Options", "DENY")
+    ///     .nest("/docs", docs_router);
+    /// ```
+    pub fn header(mut self, path: &str, name: &str, value: &str) -> Self {
+        if let Err(message) = check_path(path).and_then(|_| check_header(name, value)) {
+            panic!("{message}");
+        }
+
+        self.headers
+            .entry(path.to_owned())
+            .or_default()
+            .push((name.to_owned(), value.to_owned()));
+        self
+    }
+
+    /// Applies the tags of the router to each of its entries.
+    fn apply_router_tags(&mut self) {
+        if self.router_tags.is_empty() {
+            return;
+        }
+
+        let origins = self
+            .routes
+            .keys()
+            .map(|path| Origin::Page(path.to_owned()))
+            .chain(
+                self.redirects
+                    .keys()
+                    .map(|s| Origin::Redirect(s.to_owned())),
+            )
+            .chain(
+                self.fallbacks
+                    .keys()
+                    .map(|p| Origin::Fallback(p.to_owned())),
+            );
+        for origin in origins {
+            self.tags
+                .entry(origin)
+                .or_default()
+                .extend(self.router_tags.iter().cloned());
+        }
+
+        self.router_tags.clear();
+    }
+
+    /// Merges another `Router` into the current one.
+    ///
+    /// This method combines routes, redirects, and fallback handlers from another router.
+    /// When both routers define the same route, redirect, or fallback, the one from the current router is kept,
+    /// and the overlap is reported as a conflict when rendering.
+    /// Tags of the merged router and its entries are kept, and headers for the same path are combined.
     ///
     /// # Examples
     ///
@@ -160,24 +469,50 @@ impl Router {
     ///
     /// let merged_router = router1.merge(router2);
     /// ```
-    pub fn merge(mut self, router: Router) -> Self {
-        for (source, target) in router.redirects {
+    pub fn merge(mut self, mut router: Router) -> Self {
+        router.apply_router_tags();
+
+        self.overlaps.extend(router.overlaps);
+        self.sections.extend(router.sections);
+        for (origin, tags) in router.tags {
+            self.tags.entry(origin).or_default().extend(tags);
+        }
+        for (path, headers) in router.headers {
+            self.headers.entry(path).or_default().extend(headers);
+        }
+
+        for (source, redirect) in router.redirects {
             if self.redirects.contains_key(&source) {
-                panic!("Overlapping method route. Redirect handler for `{source}` already exists");
+                self.overlaps
+                    .push((Origin::Redirect(source.clone()), Origin::Redirect(source)));
+                continue;
             }
-            self.redirects.insert(source, target);
+            if router.site_absolute_redirects.contains(&source) {
+                self.site_absolute_redirects.insert(source.clone());
+            }
+            if router.nested_redirects.contains(&source) {
+                self.nested_redirects.insert(source.clone());
+            }
+            self.redirects.insert(source, redirect);
         }
 
         for (path, page) in router.routes {
             if self.routes.contains_key(&path) {
-                panic!("Overlapping method route. Handler for `{path}` already exists");
+                self.overlaps
+                    .push((Origin::Page(path.clone()), Origin::Page(path)));
+                continue;
+            }
+            if let Some(meta) = router.route_meta.remove(&path) {
+                self.route_meta.insert(path.clone(), meta);
             }
             self.routes.insert(path, page);
         }
 
         for (path, page) in router.fallbacks {
             if self.fallbacks.contains_key(&path) {
-                panic!("Overlapping method route. Fallback handler for `{path}` already exists");
+                self.overlaps
+                    .push((Origin::Fallback(path.clone()), Origin::Fallback(path)));
+                continue;
             }
             self.fallbacks.insert(path, page);
         }
@@ -188,11 +523,10 @@ impl Router {
     /// Nests a router under a specified path prefix.
     ///
     /// All routes, redirects, and fallback handlers from the nested router will have the prefix prepended.
+    /// Redirects to external URLs, relative targets and site-absolute targets keep their target unchanged.
+    /// Paths of images and language alternates in the metadata of pages are prefixed like redirect targets.
     /// A prefix of "/" is equivalent to no prefix.
-    ///
-    /// # Panics
-    ///
-    /// Panics if any resulting route conflicts with existing routes.
+    /// Resulting routes that overlap with existing ones are handled as in [`Router::merge`].
     ///
     /// # Examples
     ///
@@ -222,27 +556,91 @@ impl Router {
     /// // - "/blog/old" performs a redirect to "/blog/"
     /// // - The fallback route for unmatched blog paths would typically become a page at path "/blog/404"
     /// ```
-    pub fn nest(self, prefix: &str, router: Router) -> Self {
+    pub fn nest(self, prefix: &str, mut router: Router) -> Self {
         let prefix = if prefix == "/" {
             "".to_owned()
         } else {
             prefix.trim_end_matches('/').to_owned()
         };
 
-        let mut router = router;
+        router.apply_router_tags();
 
         router.redirects = router
             .redirects
             .into_iter()
-            .map(|(source, target)| (format!("{prefix}{source}"), format!("{prefix}{target}")))
+            .map(|(source, mut redirect)| {
+                if redirect.target.starts_with('/')
+                    && !is_external_url(&redirect.target)
+                    && !router.site_absolute_redirects.contains(&source)
+                {
+                    redirect.target = format!("{prefix}{}", redirect.target);
+                }
+                redirect.source = format!("{prefix}{source}");
+
+                (redirect.source.clone(), redirect)
+            })
+            .collect();
+
+        router.overlaps = router
+            .overlaps
+            .into_iter()
+            .map(|(first, second)| (first.prefixed(&prefix), second.prefixed(&prefix)))
+            .collect();
+
+        router.tags = router
+            .tags
+            .into_iter()
+            .map(|(origin, tags)| (origin.prefixed(&prefix), tags))
             .collect();
 
+        router.headers = router
+            .headers
+            .into_iter()
+            .map(|(path, headers)| (format!("{prefix}{path}"), headers))
+            .collect();
+
+        router.site_absolute_redirects = router
+            .site_absolute_redirects
+            .into_iter()
+            .map(|source| format!("{prefix}{source}"))
+            .collect();
+
+        if !prefix.is_empty() {
+            router.nested_redirects = router.redirects.keys().cloned().collect();
+        }
+
+        router.sections = router
+            .sections
+            .into_iter()
+            .map(|section| format!("{prefix}{section}"))
+            .collect();
+        if !prefix.is_empty() {
+            router.sections.insert(prefix.clone());
+        }
+
         router.routes = router
             .routes
             .into_iter()
             .map(|(path, page)| (format!("{prefix}{path}"), page))
             .collect();
 
+        let prefixed = |url: &mut String| {
+            if url.starts_with('/') && !is_external_url(url) {
+                *url = format!("{prefix}{url}");
+            }
+        };
+        router.route_meta = router
+            .route_meta
+            .into_iter()
+            .map(|(path, mut meta)| {
+                meta.images.iter_mut().for_each(prefixed);
+                meta.alternates
+                    .iter_mut()
+                    .for_each(|alternate| prefixed(&mut alternate.href));
+                (format!("{prefix}{path}"), meta)
+            })
+            .collect();
+
         router.fallbacks = router
             .fallbacks
             .into_iter()
@@ -0,0 +1,125 @@
+use crate::escape;
+
+/// A function that renders a route manifest, given the entries of every page, redirect and fallback.
+pub type RouteManifestRenderer = Box<dyn FnOnce(Vec<ManifestEntry>) -> String>;
+
+/// The kind of a route in a route manifest.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum RouteKind {
+    /// A page registered with a GET response.
+    Page,
+    /// A redirect, including the pages generated from pattern redirects.
+    Redirect,
+    /// A fallback page for unmatched paths.
+    Fallback,
+}
+
+impl RouteKind {
+    /// Returns the name of the kind used in generated manifests.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RouteKind::Page => "page",
+            RouteKind::Redirect => "redirect",
+            RouteKind::Fallback => "fallback",
+        }
+    }
+}
+
+/// A route passed to route manifest renderers.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ManifestEntry {
+    /// The route path. For fallbacks, this is the path of the fallback page.
+    pub path: String,
+    /// The kind of the route.
+    pub kind: RouteKind,
+    /// The output file of the route, relative to the output directory.
+    /// Redirects have no file when redirect pages are disabled, and pattern redirects never have one.
+    pub file: Option<String>,
+    /// The target of a redirect.
+    pub target: Option<String>,
+    /// The HTTP status code of a redirect.
+    pub status: Option<u16>,
+    /// The path prefix that a fallback page is served for.
+    pub scope: Option<String>,
+    /// Tags of the route, including those of the routers it belongs to.
+    /// Redirect pages expanded from a pattern have the tags of the pattern.
+    pub tags: Vec<String>,
+}
+
+/// Configuration for generating a route manifest file.
+pub struct RouteManifest {
+    /// The name of the output file.
+    pub file_name: &'static str,
+    /// Function that takes the entries of the manifest, sorted by path and kind, and returns the manifest's content.
+    pub content_renderer: RouteManifestRenderer,
+}
+
+impl RouteManifest {
+    /// Creates a `RouteManifest` configuration for a JSON manifest, for use in client-side navigation.
+    ///
+    /// The generated file will be named `routes.json` and contain every page, redirect and fallback of the site.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use pagebake::manifest::RouteManifest;
+    /// use pagebake::render::RenderConfig;
+    /// use pagebake::{Router, get, redirect};
+    ///
+    /// let router = Router::new()
+    ///     .route("/", get(|| "<h1>Home</h1>".to_owned()))
+    ///     .route("/old-home", redirect("/"));
+    ///
+    /// let config = RenderConfig {
+    ///     route_manifests: vec![RouteManifest::json()],
+    ///     ..Default::default()
+    /// };
+    ///
+    /// let output = router.render_to_map(config).unwrap();
+    /// assert_eq!(
+    ///     output.extra_files["routes.json"],
+    ///     r#"{
+    ///   "routes": [
+    ///     { "path": "/", "kind": "page", "file": "index.html" },
+    ///     { "path": "/old-home", "kind": "redirect", "file": "old-home.html", "target": "/", "status": 302 }
+    ///   ]
+    /// }"#
+    /// );
+    /// ```
+    pub fn json() -> Self {
+        RouteManifest {
+            file_name: "routes.json",
+            content_renderer: Box::new(|entries: Vec<ManifestEntry>| {
+                let routes = entries
+                    .iter()
+                    .map(|e| {
+                        let mut fields = vec![
+                            format!("\"path\": {}", escape::json(&e.path)),
+                            format!("\"kind\": {}", escape::json(e.kind.as_str())),
+                        ];
+                        if let Some(file) = &e.file {
+                            fields.push(format!("\"file\": {}", escape::json(file)));
+                        }
+                        if let Some(target) = &e.target {
+                            fields.push(format!("\"target\": {}", escape::json(target)));
+                        }
+                        if let Some(status) = e.status {
+                            fields.push(format!("\"status\": {status}"));
+                        }
+                        if let Some(scope) = &e.scope {
+                            fields.push(format!("\"scope\": {}", escape::json(scope)));
+                        }
+
+                        format!("    {{ {} }}", fields.join(", "))
+                    })
+                    .collect::<Vec<String>>();
+
+                if routes.is_empty() {
+                    "{\n  \"routes\": []\n}".to_owned()
+                } else {
+                    format!("{{\n  \"routes\": [\n{}\n  ]\n}}", routes.join(",\n"))
+                }
+            }),
+        }
+    }
+}
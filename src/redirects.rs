@@ -1,57 +1,173 @@
-use std::collections::{HashMap, HashSet};
+use std::{
+    cmp::Ordering,
+    collections::{HashMap, HashSet},
+};
 
-use crate::Router;
+use crate::{
+    diagnostics::Diagnostic, escape, headers::HeaderRule, render::page_file, routes::RouteFilter,
+    Router,
+};
+
+/// Suffix of redirect sources that match every path under a prefix, e.g. `/old/*`.
+pub(crate) const WILDCARD: &str = "/*";
+
+/// Placeholder at the end of a redirect target that is replaced by the part of the path matched by a wildcard.
+pub(crate) const SPLAT: &str = ":splat";
 
 /// Represents a redirection from a source path to a target path.
+///
+/// A source ending in `/*` is a pattern that matches every path under its prefix.
+/// The target of a pattern may end in `:splat`, which is replaced by the matched remainder of the path.
 #[derive(Debug, Clone)]
 pub struct Redirect {
+    /// The path that is redirected.
     pub source: String,
+    /// The path or external URL that the source redirects to.
     pub target: String,
+    /// The HTTP status code of the redirect, e.g. `301` or `302`.
+    pub status: u16,
+    /// When true, the query string and fragment of the requested URL are forwarded to the target.
+    /// Set for every redirect when `RenderConfig::preserve_query` is true.
+    ///
+    /// Redirect pages, and the lists for Caddy and nginx, append the requested query string to the target,
+    /// unless the target has a query of its own. Netlify forwards it by default, and the CSV list
+    /// writes the flag so that `import::parse_csv` reads it back. The formats of Cloudflare Pages,
+    /// Static Web Server, Firebase Hosting and S3 cannot add a query string to a target,
+    /// so the flag is left to how those hosts handle query strings.
+    pub preserve_query: bool,
 }
 
-/// A function that, given a target path, renders a page that redirects to it.
-pub type RedirectPageRenderer = Box<dyn Fn(&str) -> String>;
+/// Represents a fallback page that is served for unmatched paths under a prefix.
+#[derive(Debug, Clone)]
+pub struct Fallback {
+    /// The path prefix the fallback applies to, e.g. `/blog/`.
+    pub prefix: String,
+    /// The route path of the fallback page, e.g. `/blog/404`.
+    pub path: String,
+}
 
-/// A function that renders a list of redirects, given a vector of `Redirect` objects.
+/// A function that, given a `Redirect`, renders a page that redirects to its target.
+pub type RedirectPageRenderer = Box<dyn Fn(&Redirect) -> String>;
+
+/// A function that renders a list of redirects, given the redirects, fallback pages and HTTP headers of the site.
 /// Redirect lists can be utilized by static hosting services.
-pub type RedirectListRenderer = Box<dyn FnOnce(Vec<Redirect>) -> String>;
+pub type RedirectListRenderer = Box<dyn FnOnce(RedirectListInput) -> String>;
+
+/// The parts of a site that a redirect list is rendered from, as selected by the list's filter.
+#[derive(Debug, Clone)]
+pub struct RedirectListInput {
+    /// The redirects of the site, exact redirects first, then patterns from the most to the least specific prefix.
+    pub redirects: Vec<Redirect>,
+    /// The fallback pages of the site.
+    pub fallbacks: Vec<Fallback>,
+    /// The HTTP headers of the site's paths, sorted by path.
+    pub headers: Vec<HeaderRule>,
+}
+
+/// A function that checks whether a host can serve a redirect, returning the reason otherwise.
+pub type RedirectCheck = fn(&Redirect) -> Result<(), String>;
 
 /// Configuration for generating a redirect list file.
+///
+/// Sources and targets are percent-encoded in every list, so that spaces and non-ASCII characters
+/// do not break the format. Sequences that are already percent-encoded are kept.
 pub struct RedirectList {
     /// The name of the output file.
     pub file_name: &'static str,
-    /// Function that takes a list of `Redirect` objects and returns the redirect list's content.
+    /// Function that takes the redirects, fallbacks and header rules of the site and returns the redirect list's content.
     pub content_renderer: RedirectListRenderer,
+    /// Filter selecting the redirects, fallbacks and header rules included in the list.
+    pub filter: RouteFilter,
+    /// Optional check of the redirects included in the list, for hosts that cannot serve some of them.
+    /// Redirects that fail it are left out of the list and reported as `Diagnostic::UnsupportedRedirect`.
+    pub check: Option<RedirectCheck>,
+    /// Whether the list writes the header rules it is given, so that the host sends them.
+    pub includes_headers: bool,
+}
+
+/// How a web server treats a trailing `/` in the requested path of a page, for lists that serve pages,
+/// such as `RedirectList::for_nginx`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UrlStyle {
+    /// Pages are served with or without a trailing `/`, as in `/about` and `/about/`.
+    Any,
+    /// Pages are served without a trailing `/`, and requests with one are permanently redirected.
+    NoTrailingSlash,
+    /// Pages are served with a trailing `/`, and requests without one are permanently redirected.
+    TrailingSlash,
 }
 
 impl Redirect {
+    /// Returns `true` if the redirect points to an external absolute URL rather than a path of the site.
+    pub fn is_external(&self) -> bool {
+        is_external_url(&self.target)
+    }
+
+    /// Returns `true` if the redirect source is a pattern such as `/old/*`.
+    pub fn is_pattern(&self) -> bool {
+        pattern_prefix(&self.source).is_some()
+    }
+
     /// Returns a default redirect page renderer.
     ///
     /// This renderer produces an HTML page that immediately redirects the user to the specified target path.
     /// The output includes meta tags and JavaScript to facilitate the redirect.
     /// In case both fail, a clickable link is included that points to the target path.
+    /// The target is escaped for each context it appears in, and the page is marked as `noindex`
+    /// with the target as its canonical URL.
+    ///
+    /// When the redirect preserves the query, the script appends the query string of the requested URL
+    /// to the target and keeps its fragment, and the meta refresh is only used when scripts are disabled.
     pub fn base_redirect_page() -> RedirectPageRenderer {
-        Box::new(|target| {
+        Box::new(|redirect| {
+            let target_html = escape::xml(&redirect.target);
+            let target_js = escape::js_string(&redirect.target);
+
+            let (refresh, script) = if redirect.preserve_query {
+                (
+                    format!(
+                        r#"<noscript><meta http-equiv="refresh" content="0; url={target_html}"></noscript>"#
+                    ),
+                    format!(
+                        r##"var target = {target_js};
+            var hashIndex = target.indexOf("#");
+            var path = hashIndex < 0 ? target : target.slice(0, hashIndex);
+            var hash = window.location.hash || (hashIndex < 0 ? "" : target.slice(hashIndex));
+            if (window.location.search) {{
+                path += (path.indexOf("?") < 0 ? "?" : "&") + window.location.search.slice(1);
+            }}
+            window.location.replace(path + hash);"##
+                    ),
+                )
+            } else {
+                (
+                    format!(r#"<meta http-equiv="refresh" content="0; url={target_html}">"#),
+                    format!("window.location.replace({target_js});"),
+                )
+            };
+
             format!(
                 r#"<!DOCTYPE HTML>
 <html lang="en">
 <head>
     <meta charset="UTF-8">
-    <meta http-equiv="refresh" content="0; url={0}">
+    {1}
     <meta name="viewport" content="width=device-width, initial-scale=1.0">
+    <meta name="robots" content="noindex">
+    <link rel="canonical" href="{0}">
     <title>Page Redirection</title>
 </head>
 <body>
     <script>
         (function() {{
-            window.location.replace("{0}");
+            {2}
         }})();
     </script>
 
     <p>Redirecting to <a href="{0}">{0}</a>...</p>
 </body>
 </html>"#,
-                target
+                target_html, refresh, script
             )
         })
     }
@@ -61,78 +177,1228 @@ impl RedirectList {
     /// Creates a `RedirectList` configuration for [Cloudflare Pages](https://pages.cloudflare.com/).
     ///
     /// The generated file will be named `_redirects` and contain the list of redirects in a format
-    /// compatible with Cloudflare Pages.
+    /// compatible with Cloudflare Pages. Pattern redirects use the native splat syntax.
     pub fn for_cloudflare_pages() -> Self {
         RedirectList {
             file_name: "_redirects",
-            content_renderer: Box::new(|redirects: Vec<Redirect>| {
+            content_renderer: Box::new(|RedirectListInput { redirects, .. }: RedirectListInput| {
                 redirects
                     .iter()
-                    .map(|r| format!("{} {}", r.source, r.target))
+                    .map(|r| {
+                        format!(
+                            "{} {} {}",
+                            escape::url(&r.source),
+                            escape::url(&r.target),
+                            r.status
+                        )
+                    })
                     .collect::<Vec<String>>()
                     .join("\n")
             }),
+            filter: RouteFilter::default(),
+            check: None,
+            includes_headers: false,
         }
     }
 
     /// Creates a `RedirectList` configuration for [Static Web Server](https://static-web-server.net/).
     ///
-    /// The generated file will be named `config.toml` and contain the list of redirects as an array of tables.
+    /// The generated file will be named `config.toml` and contain the list of redirects as an array of tables,
+    /// followed by the HTTP headers of the site's paths.
+    /// Pattern redirects and header rules are written as `**` globs, and splats are replaced by the `$1` capture group.
+    /// Static Web Server only accepts the statuses `301` and `302`, so other redirects are left out and reported.
     pub fn for_static_web_server() -> Self {
         RedirectList {
             file_name: "config.toml",
-            content_renderer: Box::new(|redirects: Vec<Redirect>| {
-                let mut content = String::from("[advanced]\n\n");
+            content_renderer: Box::new(
+                |RedirectListInput {
+                     redirects, headers, ..
+                 }: RedirectListInput| {
+                    let mut sections = Vec::<String>::new();
+
+                    sections.extend(redirects.iter().map(|r| {
+                        let (source, target) = match pattern_prefix(&r.source) {
+                            Some(prefix) => (format!("{prefix}**"), apply_splat(&r.target, "$1")),
+                            None => (r.source.to_owned(), r.target.to_owned()),
+                        };
+                        let source = escape::json(&escape::url(&source));
+                        let target = escape::json(&escape::url(&target));
+                        let status = r.status;
+
+                        format!(
+                            "[[advanced.redirects]]\nsource = {source}\ndestination = {target}\nkind = {status}"
+                        )
+                    }));
+
+                    sections.extend(headers.iter().map(|rule| {
+                        let source = match pattern_prefix(&rule.path) {
+                            Some(prefix) => format!("{prefix}**"),
+                            None => rule.path.to_owned(),
+                        };
+                        let source = escape::json(&escape::url(&source));
+                        let headers = rule
+                            .headers
+                            .iter()
+                            .map(|(name, value)| format!("{} = {}", toml_key(name), escape::json(value)))
+                            .collect::<Vec<String>>()
+                            .join("\n");
+
+                        format!(
+                            "[[advanced.headers]]\nsource = {source}\n\n[advanced.headers.headers]\n{headers}"
+                        )
+                    }));
+
+                    format!("[advanced]\n\n{}", sections.join("\n\n"))
+                },
+            ),
+            filter: RouteFilter::default(),
+            check: Some(check_301_or_302),
+            includes_headers: true,
+        }
+    }
+
+    /// Creates a `RedirectList` configuration for a CSV file.
+    ///
+    /// The generated file will be named `redirects.csv` and contain a `source,target,status,preserve_query` header,
+    /// followed by one record per redirect. It can be read back with `import::parse_csv`.
+    pub fn for_csv() -> Self {
+        RedirectList {
+            file_name: "redirects.csv",
+            content_renderer: Box::new(|RedirectListInput { redirects, .. }: RedirectListInput| {
+                let mut content = String::from("source,target,status,preserve_query\n");
+
+                for r in &redirects {
+                    content.push_str(&format!(
+                        "{},{},{},{}\n",
+                        escape::csv(&escape::url(&r.source)),
+                        escape::csv(&escape::url(&r.target)),
+                        r.status,
+                        r.preserve_query
+                    ));
+                }
+
+                content
+            }),
+            filter: RouteFilter::default(),
+            check: None,
+            includes_headers: false,
+        }
+    }
+
+    /// Creates a `RedirectList` configuration for [Firebase Hosting](https://firebase.google.com/docs/hosting).
+    ///
+    /// The generated file will be named `firebase.json` and contain the list of redirects under `hosting.redirects`.
+    /// Pattern redirects capture the matched remainder of the path in a `:splat*` segment.
+    /// Other hosting settings have to be merged into this file by hand.
+    /// Firebase Hosting only accepts the statuses `301` and `302`, so other redirects are left out and reported.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use pagebake::diagnostics::Diagnostic;
+    /// use pagebake::redirects::RedirectList;
+    /// use pagebake::render::RenderConfig;
+    /// use pagebake::{Response, Router, get, permanent_redirect};
+    ///
+    /// let router = Router::new()
+    ///     .route("/", get(|| "<h1>Home</h1>".to_owned()))
+    ///     .route("/old", permanent_redirect("/"))
+    ///     .route("/older", Response::Redirect("/".to_owned(), 308));
+    ///
+    /// let config = RenderConfig {
+    ///     redirect_lists: vec![RedirectList::for_firebase_hosting()],
+    ///     ..Default::default()
+    /// };
+    ///
+    /// let output = router.render_to_map(config).unwrap();
+    /// assert!(output.extra_files["firebase.json"].contains(r#""source": "/old""#));
+    /// assert!(!output.extra_files["firebase.json"].contains("/older"));
+    /// assert_eq!(
+    ///     output.warnings,
+    ///     vec![Diagnostic::UnsupportedRedirect {
+    ///         file: "firebase.json".to_owned(),
+    ///         source: "/older".to_owned(),
+    ///         reason: "only the statuses 301 and 302 are supported, not 308".to_owned(),
+    ///     }]
+    /// );
+    /// ```
+    pub fn for_firebase_hosting() -> Self {
+        RedirectList {
+            file_name: "firebase.json",
+            content_renderer: Box::new(|RedirectListInput { redirects, .. }: RedirectListInput| {
+                let entries = redirects
+                    .iter()
+                    .map(|r| {
+                        let source = match pattern_prefix(&r.source) {
+                            Some(prefix) => format!("{}{SPLAT}*", escape::url(prefix)),
+                            None => escape::url(&r.source),
+                        };
+
+                        format!(
+                            "      {{\n        \"source\": {},\n        \"destination\": {},\n        \"type\": {}\n      }}",
+                            escape::json(&source),
+                            escape::json(&escape::url(&r.target)),
+                            r.status
+                        )
+                    })
+                    .collect::<Vec<String>>()
+                    .join(",\n");
+
+                if entries.is_empty() {
+                    "{\n  \"hosting\": {\n    \"redirects\": []\n  }\n}".to_owned()
+                } else {
+                    format!(
+                        "{{\n  \"hosting\": {{\n    \"redirects\": [\n{entries}\n    ]\n  }}\n}}"
+                    )
+                }
+            }),
+            filter: RouteFilter::default(),
+            check: Some(check_301_or_302),
+            includes_headers: false,
+        }
+    }
+
+    /// Creates a `RedirectList` configuration for [Amazon S3 static website hosting](https://docs.aws.amazon.com/AmazonS3/latest/userguide/WebsiteHosting.html).
+    ///
+    /// The generated file will be named `routing-rules.xml` and contain the `RoutingRules` element of the bucket's
+    /// website configuration.
+    ///
+    /// S3 matches conditions by key prefix, so a redirect from `/old` also applies to `/older`.
+    /// Pattern redirects replace the matched key prefix. Redirects from the root of the site,
+    /// whose empty key prefix would match every key of the bucket, are left out and reported,
+    /// as are redirects whose target has a query string or fragment.
+    pub fn for_s3_website_xml() -> Self {
+        RedirectList {
+            file_name: "routing-rules.xml",
+            content_renderer: Box::new(|RedirectListInput { redirects, .. }: RedirectListInput| {
+                let mut content = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+                content.push_str("<RoutingRules>\n");
+
+                for r in &redirects {
+                    let (condition, redirect) = s3_routing_rule(r);
+
+                    content.push_str("  <RoutingRule>\n    <Condition>\n");
+                    content.push_str(&format!(
+                        "      <KeyPrefixEquals>{}</KeyPrefixEquals>\n",
+                        escape::xml(&condition)
+                    ));
+                    content.push_str("    </Condition>\n    <Redirect>\n");
+                    for (name, value) in redirect {
+                        content
+                            .push_str(&format!("      <{name}>{}</{name}>\n", escape::xml(&value)));
+                    }
+                    content.push_str("    </Redirect>\n  </RoutingRule>\n");
+                }
+
+                content.push_str("</RoutingRules>");
+                content
+            }),
+            filter: RouteFilter::default(),
+            check: Some(check_s3_redirect),
+            includes_headers: false,
+        }
+    }
+
+    /// Creates a `RedirectList` configuration for [Amazon S3 static website hosting](https://docs.aws.amazon.com/AmazonS3/latest/userguide/WebsiteHosting.html).
+    ///
+    /// The generated file will be named `routing-rules.json` and contain the routing rules as a JSON array,
+    /// as accepted by the S3 console and the `RoutingRules` field of `aws s3api put-bucket-website`.
+    ///
+    /// S3 matches conditions by key prefix, so a redirect from `/old` also applies to `/older`.
+    /// Pattern redirects replace the matched key prefix. Redirects from the root of the site,
+    /// whose empty key prefix would match every key of the bucket, are left out and reported,
+    /// as are redirects whose target has a query string or fragment.
+    pub fn for_s3_website_json() -> Self {
+        RedirectList {
+            file_name: "routing-rules.json",
+            content_renderer: Box::new(|RedirectListInput { redirects, .. }: RedirectListInput| {
+                let rules = redirects
+                    .iter()
+                    .map(|r| {
+                        let (condition, redirect) = s3_routing_rule(r);
 
-                content.push_str(
-                    &redirects
+                        let redirect = redirect
+                            .iter()
+                            .map(|(name, value)| format!("      \"{name}\": {}", escape::json(value)))
+                            .collect::<Vec<String>>()
+                            .join(",\n");
+
+                        format!(
+                            "  {{\n    \"Condition\": {{\n      \"KeyPrefixEquals\": {}\n    }},\n    \"Redirect\": {{\n{}\n    }}\n  }}",
+                            escape::json(&condition),
+                            redirect
+                        )
+                    })
+                    .collect::<Vec<String>>()
+                    .join(",\n");
+
+                if rules.is_empty() {
+                    "[]".to_owned()
+                } else {
+                    format!("[\n{rules}\n]")
+                }
+            }),
+            filter: RouteFilter::default(),
+            check: Some(check_s3_redirect),
+            includes_headers: false,
+        }
+    }
+
+    /// Creates a `RedirectList` configuration for [Caddy](https://caddyserver.com/).
+    ///
+    /// The generated file will be named `Caddyfile` and contain a fragment to be imported into a site block.
+    /// Each redirect becomes a `redir` directive, with pattern redirects that use a splat matched by
+    /// a `path_regexp` matcher. The directives are wrapped in a `route` block, so that Caddy tries them
+    /// in the order they are written rather than sorting them by matcher. Each fallback page is served
+    /// from a `handle_errors` block for the prefix it was nested under, with more specific prefixes
+    /// taking precedence.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use pagebake::redirects::RedirectList;
+    /// use pagebake::render::RenderConfig;
+    /// use pagebake::{Router, get, permanent_redirect};
+    ///
+    /// let router = Router::new()
+    ///     .route("/", get(|| "<h1>Home</h1>".to_owned()))
+    ///     .route("/a/b/*", permanent_redirect("/c/:splat"))
+    ///     .route("/a/*", permanent_redirect("/"));
+    ///
+    /// let config = RenderConfig {
+    ///     redirect_page_renderer: None,
+    ///     redirect_lists: vec![RedirectList::for_caddy()],
+    ///     ..Default::default()
+    /// };
+    ///
+    /// let output = router.render_to_map(config).unwrap();
+    /// assert_eq!(
+    ///     output.extra_files["Caddyfile"],
+    ///     "route {\n\t@redirect0 path_regexp redirect0 ^/a/b/(.*)$\n\tredir @redirect0 /c/{re.redirect0.1} 301\n\tredir /a/* / 301\n}"
+    /// );
+    /// ```
+    pub fn for_caddy() -> Self {
+        RedirectList {
+            file_name: "Caddyfile",
+            content_renderer: Box::new(
+                |RedirectListInput {
+                     redirects,
+                     mut fallbacks,
+                     ..
+                 }: RedirectListInput| {
+                    let mut sections = Vec::<String>::new();
+
+                    let directives: Vec<String> = redirects
+                        .iter()
+                        .enumerate()
+                        .map(|(i, r)| {
+                            let query = if r.preserve_query && !r.target.contains(['?', '#']) {
+                                "{?query}"
+                            } else {
+                                ""
+                            };
+                            let status = r.status;
+
+                            match pattern_prefix(&r.source) {
+                                Some(prefix) if r.target.ends_with(SPLAT) => {
+                                    let name = format!("redirect{i}");
+                                    let regex = escape_regex(prefix);
+                                    let splat = format!("{{re.{name}.1}}");
+                                    let target = apply_splat(&escape::url(&r.target), &splat);
+
+                                    format!(
+                                        "\t@{name} path_regexp {name} ^{regex}(.*)$\n\tredir @{name} {target}{query} {status}"
+                                    )
+                                }
+                                _ => {
+                                    let source = escape::url(&r.source);
+                                    let target = escape::url(&r.target);
+
+                                    format!("\tredir {source} {target}{query} {status}")
+                                }
+                            }
+                        })
+                        .collect();
+                    if !directives.is_empty() {
+                        sections.push(format!("route {{\n{}\n}}", directives.join("\n")));
+                    }
+
+                    if !fallbacks.is_empty() {
+                        sort_fallbacks(&mut fallbacks);
+
+                        let handlers = fallbacks
+                            .iter()
+                            .map(|f| {
+                                let matcher = if f.prefix == "/" {
+                                    String::new()
+                                } else {
+                                    format!("{}* ", escape::url(&f.prefix))
+                                };
+
+                                format!(
+                                    "\thandle {}{{\n\t\trewrite * /{}\n\t\tfile_server\n\t}}",
+                                    matcher,
+                                    escape::url(&page_file(&f.path))
+                                )
+                            })
+                            .collect::<Vec<String>>()
+                            .join("\n");
+
+                        sections.push(format!("handle_errors 404 {{\n{handlers}\n}}"));
+                    }
+
+                    sections.join("\n\n")
+                },
+            ),
+            filter: RouteFilter::default(),
+            check: None,
+            includes_headers: false,
+        }
+    }
+
+    /// Creates a `RedirectList` configuration for [Netlify](https://www.netlify.com/).
+    ///
+    /// The generated file will be named `_redirects` and contain the list of redirects in the same format
+    /// as for Cloudflare Pages, followed by a `404` rule for each fallback page, with more specific prefixes first.
+    /// Netlify does not apply rules to paths that have a file, so redirect pages should be disabled.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use pagebake::redirects::RedirectList;
+    /// use pagebake::render::RenderConfig;
+    /// use pagebake::{Router, get, permanent_redirect};
+    ///
+    /// let blog_router = Router::new()
+    ///     .route("/", get(|| "<h1>Blog</h1>".to_owned()))
+    ///     .fallback(|| "<h1>Post not found</h1>".to_owned());
+    ///
+    /// let router = Router::new()
+    ///     .route("/", get(|| "<h1>Home</h1>".to_owned()))
+    ///     .route("/news", permanent_redirect("/blog/"))
+    ///     .nest("/blog", blog_router);
+    ///
+    /// let config = RenderConfig {
+    ///     redirect_page_renderer: None,
+    ///     redirect_lists: vec![RedirectList::for_netlify()],
+    ///     ..Default::default()
+    /// };
+    ///
+    /// let output = router.render_to_map(config).unwrap();
+    /// assert_eq!(
+    ///     output.extra_files["_redirects"],
+    ///     "/news /blog/ 301\n/blog/* /blog/404.html 404"
+    /// );
+    /// ```
+    pub fn for_netlify() -> Self {
+        RedirectList {
+            file_name: "_redirects",
+            content_renderer: Box::new(
+                |RedirectListInput {
+                     redirects,
+                     mut fallbacks,
+                     ..
+                 }: RedirectListInput| {
+                    sort_fallbacks(&mut fallbacks);
+
+                    redirects
                         .iter()
                         .map(|r| {
                             format!(
-                                "[[advanced.redirects]]\nsource = \"{}\"\ndestination = \"{}\"\nkind = 302",
-                                r.source, r.target
+                                "{} {} {}",
+                                escape::url(&r.source),
+                                escape::url(&r.target),
+                                r.status
                             )
                         })
+                        .chain(fallbacks.iter().map(|f| {
+                            format!(
+                                "{}* /{} 404",
+                                escape::url(&f.prefix),
+                                escape::url(&page_file(&f.path))
+                            )
+                        }))
                         .collect::<Vec<String>>()
-                        .join("\n\n"),
-                );
+                        .join("\n")
+                },
+            ),
+            filter: RouteFilter::default(),
+            check: None,
+            includes_headers: false,
+        }
+    }
 
-                content
-            }),
+    /// Creates a `RedirectList` configuration for [nginx](https://nginx.org/).
+    ///
+    /// The generated file will be named `nginx.conf` and contain a fragment to be included in a `server` block
+    /// whose `root` is the output directory. Pages are served from their `.html` files, with a trailing `/`
+    /// of the requested path handled as set by `url_style`. Each redirect becomes a `location` with a `return`
+    /// directive, with pattern redirects matched by a regular expression against the requested path, and each
+    /// fallback page is set as the `error_page` of the prefix it was nested under. Headers are not included.
+    /// With `UrlStyle::TrailingSlash`, exact redirects are matched by a regular expression as well,
+    /// so that they apply with or without a trailing `/`.
+    /// Unlike in other lists, sources are not percent-encoded, since nginx matches locations against
+    /// the decoded path.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use pagebake::redirects::{RedirectList, UrlStyle};
+    /// use pagebake::render::RenderConfig;
+    /// use pagebake::{Router, get, permanent_redirect};
+    ///
+    /// let docs_router = Router::new()
+    ///     .route("/", get(|| "<h1>Docs</h1>".to_owned()))
+    ///     .fallback(|| "<h1>Page not found</h1>".to_owned());
+    ///
+    /// let router = Router::new()
+    ///     .route("/", get(|| "<h1>Home</h1>".to_owned()))
+    ///     .route("/old", permanent_redirect("/"))
+    ///     .route("/guide/*", permanent_redirect("/docs/:splat"))
+    ///     .nest("/docs", docs_router);
+    ///
+    /// let config = RenderConfig {
+    ///     redirect_lists: vec![RedirectList::for_nginx(UrlStyle::Any)],
+    ///     ..Default::default()
+    /// };
+    ///
+    /// let output = router.render_to_map(config).unwrap();
+    /// assert_eq!(
+    ///     output.extra_files["nginx.conf"],
+    ///     r#"location / {
+    ///     rewrite ^(/.+)/$ $1 last;
+    ///     try_files $uri $uri.html =404;
+    /// }
+    ///
+    /// location "/docs/" {
+    ///     error_page 404 /docs/404.html;
+    ///     rewrite ^(/.+)/$ $1 last;
+    ///     try_files $uri $uri.html =404;
+    /// }
+    ///
+    /// location = "/old" {
+    ///     return 301 "/";
+    /// }
+    ///
+    /// location ~ "^/guide/(.*)$" {
+    ///     return 301 "/docs/$1";
+    /// }"#
+    /// );
+    /// ```
+    ///
+    /// With `UrlStyle::TrailingSlash`, requests for `/about` are redirected to `/about/`:
+    ///
+    /// ```rust
+    /// use pagebake::redirects::{RedirectList, UrlStyle};
+    /// use pagebake::render::RenderConfig;
+    /// use pagebake::{Router, get, permanent_redirect};
+    ///
+    /// let router = Router::new()
+    ///     .route("/about", get(|| "<h1>About</h1>".to_owned()))
+    ///     .route("/old", permanent_redirect("/about/"));
+    ///
+    /// let config = RenderConfig {
+    ///     redirect_lists: vec![RedirectList::for_nginx(UrlStyle::TrailingSlash)],
+    ///     ..Default::default()
+    /// };
+    ///
+    /// let output = router.render_to_map(config).unwrap();
+    /// assert_eq!(
+    ///     output.extra_files["nginx.conf"],
+    ///     r#"location / {
+    ///     if (-f $request_filename.html) {
+    ///         rewrite ^(.*)$ $1/ permanent;
+    ///     }
+    ///     rewrite ^(/.+)/$ $1 break;
+    ///     try_files $uri $uri.html =404;
+    /// }
+    ///
+    /// location ~ "^/old/?$" {
+    ///     return 301 "/about/";
+    /// }"#
+    /// );
+    /// ```
+    pub fn for_nginx(url_style: UrlStyle) -> Self {
+        RedirectList {
+            file_name: "nginx.conf",
+            content_renderer: Box::new(
+                move |RedirectListInput {
+                          redirects,
+                          mut fallbacks,
+                          ..
+                      }: RedirectListInput| {
+                    sort_fallbacks(&mut fallbacks);
+
+                    // Strings are quoted, and `$` is encoded in targets so that it is not read as a variable.
+                    // Sources and prefixes are not percent-encoded, as nginx decodes the requested path
+                    // before matching locations.
+                    let quote = |text: &str| {
+                        format!("\"{}\"", text.replace('\\', "\\\\").replace('"', "\\\""))
+                    };
+                    let target = |r: &Redirect, splat: &str| {
+                        let query = if r.preserve_query && !r.target.contains(['?', '#']) {
+                            "$is_args$args"
+                        } else {
+                            ""
+                        };
+                        let target = escape::url(&r.target).replace('$', "%24");
+                        quote(&format!("{}{query}", apply_splat(&target, splat)))
+                    };
+
+                    // The trailing `/` is only handled in prefix locations, so that pattern redirects,
+                    // whose regular expressions are matched first, see the requested path.
+                    // Paths with a trailing `/` are rewritten to their page without searching the locations
+                    // again when pages without one are redirected, which would otherwise loop.
+                    let serve = match url_style {
+                        UrlStyle::Any => "rewrite ^(/.+)/$ $1 last;",
+                        UrlStyle::NoTrailingSlash => "rewrite ^(/.+)/$ $1 permanent;",
+                        UrlStyle::TrailingSlash => concat!(
+                            "if (-f $request_filename.html) {\n",
+                            "        rewrite ^(.*)$ $1/ permanent;\n",
+                            "    }\n",
+                            "    rewrite ^(/.+)/$ $1 break;",
+                        ),
+                    };
+                    let serve = format!("{serve}\n    try_files $uri $uri.html =404;");
+                    let error_page = |f: &Fallback| {
+                        format!("error_page 404 /{};", escape::url(&page_file(&f.path)))
+                    };
+
+                    let mut sections = vec![];
+                    let root = fallbacks.iter().find(|f| f.prefix == "/");
+                    sections.push(format!(
+                        "{}location / {{\n    {serve}\n}}",
+                        root.map_or("".to_owned(), |f| format!("{}\n\n", error_page(f)))
+                    ));
+                    sections.extend(fallbacks.iter().filter(|f| f.prefix != "/").map(|f| {
+                        format!(
+                            "location {} {{\n    {}\n    {serve}\n}}",
+                            quote(&f.prefix),
+                            error_page(f)
+                        )
+                    }));
+
+                    sections.extend(redirects.iter().map(|r| match pattern_prefix(&r.source) {
+                        Some(prefix) => format!(
+                            "location ~ {} {{\n    return {} {};\n}}",
+                            quote(&format!("^{}(.*)$", escape_regex(prefix))),
+                            r.status,
+                            target(r, "$1")
+                        ),
+                        None if url_style == UrlStyle::TrailingSlash => {
+                            let source = match r.source.trim_end_matches('/') {
+                                "" => "^/$".to_owned(),
+                                source => format!("^{}/?$", escape_regex(source)),
+                            };
+                            format!(
+                                "location ~ {} {{\n    return {} {};\n}}",
+                                quote(&source),
+                                r.status,
+                                target(r, "")
+                            )
+                        }
+                        None => {
+                            let source = match r.source.trim_end_matches('/') {
+                                "" => "/",
+                                source => source,
+                            };
+                            format!(
+                                "location = {} {{\n    return {} {};\n}}",
+                                quote(source),
+                                r.status,
+                                target(r, "")
+                            )
+                        }
+                    }));
+
+                    sections.join("\n\n")
+                },
+            ),
+            filter: RouteFilter::default(),
+            check: None,
+            includes_headers: false,
+        }
+    }
+}
+
+/// URL schemes that run code when navigated to, which are not accepted as redirect targets.
+const UNSAFE_SCHEMES: [&str; 3] = ["javascript", "data", "vbscript"];
+
+/// Returns `true` if `target` is an absolute URL with a scheme, such as `https://example.com/`
+/// or `mailto:hello@example.com`, or a protocol-relative URL such as `//example.com/`.
+pub(crate) fn is_external_url(target: &str) -> bool {
+    if target.starts_with("//") {
+        return true;
+    }
+
+    match target.split_once(':') {
+        Some((scheme, _)) => {
+            let mut chars = scheme.chars();
+            chars.next().is_some_and(|c| c.is_ascii_alphabetic())
+                && chars.all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.'))
+        }
+        None => false,
+    }
+}
+
+/// Checks that `path` is a valid route path, returning a description of the problem otherwise.
+pub(crate) fn check_path(path: &str) -> Result<(), String> {
+    if path.is_empty() {
+        return Err("Paths must start with a `/`. Use \"/\" for root routes".to_owned());
+    } else if !path.starts_with('/') {
+        return Err("Paths must start with a `/`".to_owned());
+    }
+
+    let is_pattern = pattern_prefix(path).is_some();
+    if path.matches('*').count() > usize::from(is_pattern) {
+        return Err(
+            "Wildcards are only allowed as the last segment of a path, as in `/old/*`".to_owned(),
+        );
+    }
+
+    Ok(())
+}
+
+/// Checks that a redirect from `source` to `target` is valid, returning a description of the problem otherwise.
+pub(crate) fn check_redirect(source: &str, target: &str, status: u16) -> Result<(), String> {
+    check_path(source)?;
+
+    if target.is_empty() {
+        return Err("Redirect targets must not be empty".to_owned());
+    }
+
+    // Targets are written into the script and meta refresh of redirect pages, where these schemes run code.
+    let scheme = target.split_once(':').map(|(scheme, _)| scheme);
+    if is_external_url(target)
+        && scheme.is_some_and(|scheme| {
+            UNSAFE_SCHEMES
+                .iter()
+                .any(|unsafe_scheme| scheme.eq_ignore_ascii_case(unsafe_scheme))
+        })
+    {
+        return Err(format!(
+            "Redirect targets must not use the `{}:` scheme",
+            scheme.unwrap().to_ascii_lowercase()
+        ));
+    }
+
+    let splats = target.matches(SPLAT).count();
+    if splats > 1 || (splats == 1 && !(pattern_prefix(source).is_some() && target.ends_with(SPLAT)))
+    {
+        return Err(format!(
+            "`{SPLAT}` is only allowed at the end of targets of wildcard paths"
+        ));
+    }
+
+    if !(300..400).contains(&status) {
+        return Err(format!(
+            "Redirect status must be a 3xx code. Found `{status}`"
+        ));
+    }
+
+    Ok(())
+}
+
+/// Returns `true` if `target` is a path relative to the redirect source, such as `../contact`.
+pub(crate) fn is_relative(target: &str) -> bool {
+    !target.starts_with('/') && !is_external_url(target)
+}
+
+/// Resolves a relative redirect target such as `../contact` against the path of its source.
+///
+/// Dot segments are removed as described in [RFC 3986](https://www.rfc-editor.org/rfc/rfc3986#section-5.2.4),
+/// without going above the root of the site.
+pub(crate) fn resolve_relative(source: &str, target: &str) -> String {
+    let (path, rest) = target.split_at(target.find(['?', '#']).unwrap_or(target.len()));
+    let base = &source[..source.rfind('/').map_or(0, |i| i + 1)];
+    let joined = format!("{base}{path}");
+
+    let parts: Vec<&str> = joined.trim_start_matches('/').split('/').collect();
+    let mut segments = Vec::<&str>::new();
+
+    for (i, part) in parts.iter().enumerate() {
+        let is_last = i + 1 == parts.len();
+
+        match *part {
+            "." => {}
+            ".." => {
+                segments.pop();
+            }
+            part => {
+                segments.push(part);
+                continue;
+            }
+        }
+
+        if is_last {
+            segments.push("");
+        }
+    }
+
+    format!("/{}{rest}", segments.join("/"))
+}
+
+/// Returns the prefix matched by a pattern source such as `/old/*`, including the trailing `/`.
+pub(crate) fn pattern_prefix(source: &str) -> Option<&str> {
+    source
+        .strip_suffix(WILDCARD)
+        .map(|prefix| &source[..prefix.len() + 1])
+}
+
+/// Replaces the splat placeholder at the end of `target`, if any, with `splat`.
+pub(crate) fn apply_splat(target: &str, splat: &str) -> String {
+    match target.strip_suffix(SPLAT) {
+        Some(prefix) => format!("{prefix}{splat}"),
+        None => target.to_owned(),
+    }
+}
+
+/// Sorts redirects in the order hosts should match them:
+/// exact redirects first, then patterns from the most to the least specific prefix.
+pub(crate) fn sort_redirects(redirects: &mut [Redirect]) {
+    redirects.sort_by(|a, b| {
+        a.is_pattern()
+            .cmp(&b.is_pattern())
+            .then_with(|| match a.is_pattern() {
+                true => b.source.len().cmp(&a.source.len()),
+                false => Ordering::Equal,
+            })
+            .then_with(|| a.source.cmp(&b.source))
+    });
+}
+
+/// Sorts fallbacks from the most to the least specific prefix, as needed by hosts that match them in order.
+pub(crate) fn sort_fallbacks(fallbacks: &mut [Fallback]) {
+    fallbacks.sort_by(|a, b| {
+        b.prefix
+            .len()
+            .cmp(&a.prefix.len())
+            .then(a.prefix.cmp(&b.prefix))
+    });
+}
+
+/// Expands pattern redirects into one redirect for each of the given pages covered by their target.
+///
+/// `redirects` must be sorted with `sort_redirects`. Paths that are already pages or exact redirects,
+/// or are matched by a more specific pattern, are skipped. Patterns whose target is external
+/// or has no splat cannot be expanded.
+pub(crate) fn expand_patterns(redirects: &[Redirect], pages: &[String]) -> Vec<Redirect> {
+    let patterns: Vec<&Redirect> = redirects.iter().filter(|r| r.is_pattern()).collect();
+    let mut taken: HashSet<String> = redirects
+        .iter()
+        .filter(|r| !r.is_pattern())
+        .map(|r| r.source.to_owned())
+        .chain(pages.iter().cloned())
+        .collect();
+
+    let mut expanded = Vec::new();
+
+    for pattern in &patterns {
+        let Some(target_prefix) = pattern.target.strip_suffix(SPLAT) else {
+            continue;
+        };
+        if pattern.is_external() {
+            continue;
+        }
+        let source_prefix = pattern_prefix(&pattern.source).unwrap();
+
+        for page in pages {
+            let Some(splat) = page.strip_prefix(target_prefix) else {
+                continue;
+            };
+            let source = format!("{source_prefix}{splat}");
+
+            let matched_by = patterns
+                .iter()
+                .find(|p| source.starts_with(pattern_prefix(&p.source).unwrap()));
+            if taken.contains(&source) || matched_by.is_none_or(|p| p.source != pattern.source) {
+                continue;
+            }
+
+            taken.insert(source.clone());
+            expanded.push(Redirect {
+                source,
+                target: page.to_owned(),
+                status: pattern.status,
+                preserve_query: pattern.preserve_query,
+            });
+        }
+    }
+
+    expanded
+}
+
+/// Returns a diagnostic for every internal redirect whose target is not served by the site.
+///
+/// Targets are looked up among the redirects themselves, the `pages` and the additional `files`
+/// in the output. Query strings and fragments are ignored, and targets ending in a splat are not checked.
+pub(crate) fn dangling_redirects(
+    redirects: &[Redirect],
+    pages: &[String],
+    files: &[String],
+) -> Vec<Diagnostic> {
+    let page_files: HashSet<String> = pages.iter().map(|p| page_file(p)).collect();
+    let files: HashSet<&str> = files.iter().map(|f| f.trim_start_matches('/')).collect();
+
+    let exists = |target: &str| {
+        redirects.iter().any(|r| match pattern_prefix(&r.source) {
+            Some(prefix) => target.starts_with(prefix),
+            None => r.source == target,
+        }) || page_files.contains(&page_file(target))
+            || files.contains(target.trim_start_matches('/'))
+    };
+
+    redirects
+        .iter()
+        .filter(|r| !r.is_external() && !r.target.ends_with(SPLAT))
+        .filter(|r| {
+            let path = r.target.split(['?', '#']).next().unwrap_or_default();
+            !exists(path)
+        })
+        .map(|r| Diagnostic::DanglingRedirect {
+            source: r.source.to_owned(),
+            target: r.target.to_owned(),
+        })
+        .collect()
+}
+
+/// Returns `name` as a TOML key, quoting it if it contains characters that are not allowed in bare keys.
+fn toml_key(name: &str) -> String {
+    if name
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+    {
+        name.to_owned()
+    } else {
+        escape::json(name)
+    }
+}
+
+/// Escapes the characters of `text` that have a special meaning in regular expressions.
+fn escape_regex(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+
+    for c in text.chars() {
+        if "\\.+*?()|[]{}^$".contains(c) {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+
+    escaped
+}
+
+/// Checks that a redirect uses one of the statuses `301` and `302`, the only ones some hosts accept.
+fn check_301_or_302(redirect: &Redirect) -> Result<(), String> {
+    match redirect.status {
+        301 | 302 => Ok(()),
+        status => Err(format!(
+            "only the statuses 301 and 302 are supported, not {status}"
+        )),
+    }
+}
+
+/// Checks that a redirect can be written as an S3 routing rule that only matches its source.
+fn check_s3_redirect(redirect: &Redirect) -> Result<(), String> {
+    match pattern_prefix(&redirect.source).unwrap_or(&redirect.source) {
+        "/" => Err(
+            "an S3 routing rule for the root of the site would redirect every key of the bucket"
+                .to_owned(),
+        ),
+        _ if redirect.is_external() && !redirect.target.contains("//") => Err(format!(
+            "S3 routing rules can only redirect to paths and web URLs, not `{}`",
+            redirect.target
+        )),
+        _ if redirect.target.contains(['?', '#']) => Err(
+            "S3 routing rules replace the object key, which cannot carry a query string or fragment"
+                .to_owned(),
+        ),
+        _ => Ok(()),
+    }
+}
+
+/// Splits a redirect into the key prefix condition and the redirect fields of an S3 routing rule.
+///
+/// S3 object keys do not start with a `/`, so it is stripped from both paths.
+/// Both paths are percent-encoded like those of every other list.
+/// External targets are split into the protocol, host name and key of the URL.
+/// Pattern redirects with a splat replace the key prefix instead of the whole key.
+fn s3_routing_rule(redirect: &Redirect) -> (String, Vec<(&'static str, String)>) {
+    let condition = escape::url(pattern_prefix(&redirect.source).unwrap_or(&redirect.source))
+        .trim_start_matches('/')
+        .to_owned();
+    let target = escape::url(&redirect.target);
+    let mut fields = Vec::new();
+
+    let key = if redirect.is_external() {
+        let (protocol, rest) = match target.split_once("//") {
+            Some((scheme, rest)) => (scheme.trim_end_matches(':'), rest),
+            None => ("", target.as_str()),
+        };
+        let (host, key) = rest.split_once('/').unwrap_or((rest, ""));
+
+        if !protocol.is_empty() {
+            fields.push(("Protocol", protocol.to_owned()));
         }
+        fields.push(("HostName", host.to_owned()));
+        key
+    } else {
+        target.trim_start_matches('/')
+    };
+
+    match key.strip_suffix(SPLAT) {
+        Some(key_prefix) if redirect.is_pattern() => {
+            fields.push(("ReplaceKeyPrefixWith", key_prefix.to_owned()))
+        }
+        _ => fields.push(("ReplaceKeyWith", key.to_owned())),
     }
+    fields.push(("HttpRedirectCode", redirect.status.to_string()));
+
+    (condition, fields)
 }
 
 impl Router {
-    /// Resolves chained redirects into their final target path.
+    /// Returns the redirect rule that applies to `target`, along with the path it redirects to.
     ///
-    /// This method traverses redirect chains to avoid cycles and ensure that each source path maps
-    /// to the ultimate target path.
-    ///
-    /// # Panics
+    /// Exact redirects take precedence over patterns, and more specific patterns over less specific ones.
+    /// A target ending in a splat stands for every path under its prefix, so only patterns covering
+    /// that whole prefix apply to it. Existing pages are never redirected by patterns.
+    fn next_redirect(&self, target: &str) -> Option<(&String, String)> {
+        if is_external_url(target) || self.routes.contains_key(target) {
+            return None;
+        }
+
+        let (path, splat) = match target.strip_suffix(SPLAT) {
+            Some(prefix) => (prefix, SPLAT),
+            None => match self.redirects.get_key_value(target) {
+                Some((source, next)) => return Some((source, next.target.to_owned())),
+                None => (target, ""),
+            },
+        };
+
+        self.redirects
+            .iter()
+            .filter_map(|(source, next)| {
+                let prefix = pattern_prefix(source)?;
+                let rest = path.strip_prefix(prefix)?;
+                Some((
+                    source,
+                    prefix.len(),
+                    apply_splat(&next.target, &format!("{rest}{splat}")),
+                ))
+            })
+            .max_by_key(|(_, len, _)| *len)
+            .map(|(source, _, next)| (source, next))
+    }
+
+    /// Follows the redirect chain that starts at `source`.
     ///
-    /// Panics if a cycle is detected in the redirect chain.
-    pub(crate) fn resolve_redirects(&self) -> HashMap<String, String> {
-        let mut resolved = HashMap::<String, String>::new();
+    /// Returns every path along the chain, starting with `source` and ending with the final target.
+    /// If the chain runs into a cycle, the paths forming the cycle are returned as an error instead,
+    /// ending with the path or pattern that closes it.
+    fn redirect_chain(&self, source: &String) -> Result<Vec<String>, Vec<String>> {
+        let mut rules = vec![source];
+        let mut path = vec![source.to_owned(), self.redirects[source].target.to_owned()];
+
+        while let Some((rule, next_target)) = self.next_redirect(path.last().unwrap()) {
+            if let Some(start) = rules.iter().position(|r| *r == rule) {
+                let mut cycle = path.split_off(start);
+                if cycle.last() != Some(rule) {
+                    cycle.push(rule.to_owned());
+                }
+                return Err(cycle);
+            }
 
-        for (source, target) in &self.redirects {
-            let mut visited = HashSet::<&String>::new();
-            visited.insert(&source);
+            rules.push(rule);
+            path.push(next_target);
+        }
 
-            let mut final_target = target;
+        Ok(path)
+    }
+
+    /// Checks every redirect chain for cycles and, if `max_length` is set, for chains longer than it.
+    ///
+    /// Returns the cycles and the chains that are too long, each reported once. A chain that continues
+    /// a longer chain is only reported from the head of the longer one.
+    pub(crate) fn check_redirect_chains(
+        &self,
+        max_length: Option<usize>,
+    ) -> (Vec<Diagnostic>, Vec<Diagnostic>) {
+        let mut sources: Vec<&String> = self.redirects.keys().collect();
+        sources.sort();
 
-            while let Some(next_target) = self.redirects.get(final_target) {
-                if visited.contains(next_target) {
-                    panic!("Cycle in redirects starting at `{next_target}`");
+        let mut cycles = Vec::<Vec<String>>::new();
+        let mut long_chains = Vec::<Vec<String>>::new();
+
+        for source in sources {
+            match self.redirect_chain(source) {
+                Ok(chain) => {
+                    if max_length.is_some_and(|max_length| chain.len() - 1 > max_length) {
+                        long_chains.push(chain);
+                    }
                 }
+                Err(mut cycle) => {
+                    // Rotate cycles of exact paths to start at their smallest path, so that they are only reported once.
+                    if cycle.first() == cycle.last() {
+                        cycle.pop();
+                        let start = (0..cycle.len()).min_by_key(|&i| &cycle[i]).unwrap();
+                        cycle.rotate_left(start);
+                        cycle.push(cycle[0].clone());
+                    }
 
-                visited.insert(final_target);
-                final_target = next_target;
+                    if !cycles.contains(&cycle) {
+                        cycles.push(cycle);
+                    }
+                }
             }
+        }
+
+        let heads: Vec<Vec<String>> = long_chains
+            .iter()
+            .filter(|chain| {
+                !long_chains
+                    .iter()
+                    .any(|other| other.len() > chain.len() && other.ends_with(chain))
+            })
+            .cloned()
+            .collect();
+
+        let cycles = cycles
+            .into_iter()
+            .map(|cycle| Diagnostic::RedirectCycle { cycle })
+            .collect();
+        let long_chains = heads
+            .into_iter()
+            .map(|chain| Diagnostic::LongRedirectChain { chain })
+            .collect();
+
+        (cycles, long_chains)
+    }
+
+    /// Resolves chained redirects into their final target path.
+    ///
+    /// This method traverses redirect chains to ensure that each source path maps to the ultimate target path.
+    /// External targets always end a chain, and pattern redirects are followed for the paths they match.
+    /// Redirects that run into a cycle are left unchanged.
+    pub(crate) fn resolve_redirects(&self) -> HashMap<String, Redirect> {
+        self.redirects
+            .iter()
+            .map(|(source, redirect)| {
+                let target = match self.redirect_chain(source) {
+                    Ok(mut chain) => chain.pop().unwrap(),
+                    Err(_) => redirect.target.to_owned(),
+                };
+
+                (
+                    source.to_owned(),
+                    Redirect {
+                        target,
+                        ..redirect.clone()
+                    },
+                )
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-            resolved.insert(source.to_owned(), final_target.to_owned());
+    #[test]
+    fn check_redirect_rejects_script_targets() {
+        for target in [
+            "javascript:alert(1)",
+            "JavaScript:alert(1)",
+            "data:text/html,<script>alert(1)</script>",
+            "vbscript:msgbox(1)",
+        ] {
+            assert!(check_redirect("/old", target, 301).is_err(), "{target}");
         }
 
-        resolved
+        assert!(check_redirect("/old", "https://example.com/", 301).is_ok());
+        assert!(check_redirect("/old", "mailto:hello@example.com", 301).is_ok());
+        assert!(check_redirect("/old", "/data:file", 301).is_ok());
+    }
+
+    #[test]
+    fn resolve_relative_follows_dot_segments() {
+        let cases = [
+            ("/blog/post", "other", "/blog/other"),
+            ("/blog/post", "../about", "/about"),
+            ("/blog/2024/post", "../../about", "/about"),
+            ("/blog/post", "a/./b/../c", "/blog/a/c"),
+            // Segments above the root are dropped.
+            ("/post", "../../about", "/about"),
+            ("/blog/post", "..", "/"),
+        ];
+        for (source, target, resolved) in cases {
+            assert_eq!(resolve_relative(source, target), resolved, "{target}");
+        }
+    }
+
+    #[test]
+    fn resolve_relative_keeps_trailing_slashes() {
+        let cases = [
+            ("/blog/", "post", "/blog/post"),
+            ("/blog/docs/", "../guide/", "/blog/guide/"),
+            ("/blog/post", "./", "/blog/"),
+            ("/blog/post", "../", "/"),
+            ("/blog/post", "archive/.", "/blog/archive/"),
+        ];
+        for (source, target, resolved) in cases {
+            assert_eq!(resolve_relative(source, target), resolved, "{target}");
+        }
+    }
+
+    #[test]
+    fn resolve_relative_keeps_query_and_fragment() {
+        assert_eq!(
+            resolve_relative("/blog/post", "../about?from=blog#team"),
+            "/about?from=blog#team"
+        );
+        assert_eq!(
+            resolve_relative("/blog/post", "other/?a=../b"),
+            "/blog/other/?a=../b"
+        );
+    }
+
+    #[test]
+    fn long_chains_are_reported_from_their_head() {
+        let router = Router::new()
+            .route("/a", crate::redirect("/b"))
+            .route("/b", crate::redirect("/c"))
+            .route("/c", crate::redirect("/d"))
+            .route("/d", crate::redirect("/e"))
+            .route("/x", crate::redirect("/c"));
+
+        let (cycles, long_chains) = router.check_redirect_chains(Some(2));
+        assert!(cycles.is_empty());
+        assert_eq!(
+            long_chains,
+            vec![
+                Diagnostic::LongRedirectChain {
+                    chain: ["/a", "/b", "/c", "/d", "/e"].map(String::from).to_vec(),
+                },
+                Diagnostic::LongRedirectChain {
+                    chain: ["/x", "/c", "/d", "/e"].map(String::from).to_vec(),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn check_s3_redirect_rejects_queries() {
+        let redirect = |target: &str| Redirect {
+            source: "/old".to_owned(),
+            target: target.to_owned(),
+            status: 301,
+            preserve_query: false,
+        };
+
+        assert!(check_s3_redirect(&redirect("https://example.com/search?q=a")).is_err());
+        assert!(check_s3_redirect(&redirect("/new#section")).is_err());
+        assert!(check_s3_redirect(&redirect("https://example.com/new")).is_ok());
+        assert!(check_s3_redirect(&redirect("/new")).is_ok());
     }
 }
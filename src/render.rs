@@ -1,17 +1,139 @@
-use std::{collections::HashMap, fs, io, path::Path, rc::Rc};
+use std::{
+    collections::{HashMap, HashSet},
+    fs, io,
+    path::Path,
+    rc::Rc,
+};
 
 use crate::{
-    redirects::{Redirect, RedirectList, RedirectPageRenderer},
-    routes::RouteList,
+    csp::{ContentSecurityPolicy, CspOutput, CSP_HEADER},
+    diagnostics::{Diagnostic, Generator, Level, Origin, RenderError, Report},
+    headers::{HeaderList, HeaderRule},
+    hosts::HostProfile,
+    manifest::{ManifestEntry, RouteKind, RouteManifest},
+    redirects::pattern_prefix,
+    redirects::{
+        dangling_redirects, expand_patterns, is_relative, resolve_relative, sort_redirects,
+        Fallback, Redirect, RedirectList, RedirectListInput, RedirectPageRenderer, WILDCARD,
+    },
+    robots::Robots,
+    routes::{RouteEntry, RouteFilter, RouteList, RouteMeta},
+    search::SearchIndex,
     Router,
 };
 
+/// A function that renders a redirect or header list, given the header rules generated from the rendered pages.
+type ListGenerator = Box<dyn FnOnce(Vec<HeaderRule>) -> String>;
+
+/// A function that renders a file.
+type FileGenerator = Box<dyn FnOnce() -> String>;
+
+/// A route list, along with the names and contents of the files it is split into and the name of its index file.
+type RenderedRouteList = (RouteList, Vec<(String, String)>, Option<String>);
+
 /// Mapping of route paths to rendering functions.
 struct RenderMap {
     /// Maps route paths to functions that return HTML content.
-    pages: HashMap<String, Box<dyn FnOnce() -> String>>,
-    /// Maps additional file paths (e.g. redirect lists) to their content generators.
-    extra_files: HashMap<String, Box<dyn FnOnce() -> String>>,
+    pages: HashMap<String, FileGenerator>,
+    /// Maps additional file paths (e.g. route lists) to their content generators.
+    extra_files: HashMap<String, FileGenerator>,
+    /// Maps redirect and header list file paths to their content generators,
+    /// which take the header rules generated from the rendered pages.
+    lists: HashMap<String, ListGenerator>,
+    /// Configuration for the Content-Security-Policy, which is generated from the rendered pages.
+    content_security_policy: Option<ContentSecurityPolicy>,
+    /// `Content-Security-Policy` headers set for single pages, which are merged into the generated policies.
+    header_policies: HashMap<String, String>,
+    /// Problems found by checks configured with `Level::Warn`.
+    warnings: Vec<Diagnostic>,
+    /// Configuration for the search index, which is generated from the rendered pages.
+    search_index: Option<SearchIndex>,
+    /// Route paths of the pages included in the search index.
+    searchable: HashSet<String>,
+    /// Configurations for files generated from the rendered output.
+    post_render_lists: Vec<PostRenderList>,
+    /// Manifest entries of the site's routes, which are passed to post-render lists.
+    manifest: Vec<ManifestEntry>,
+}
+
+impl RenderMap {
+    /// Returns `true` if files are generated from the rendered pages, so that all pages are kept in memory.
+    fn is_buffered(&self) -> bool {
+        self.content_security_policy.is_some()
+            || self.search_index.is_some()
+            || !self.post_render_lists.is_empty()
+    }
+
+    /// Renders every page and file and writes each one to `output_path` as soon as it is rendered.
+    ///
+    /// Must only be used when the map is not buffered, as no files are generated from the rendered pages.
+    fn write(self, output_path: &Path) -> io::Result<Vec<Diagnostic>> {
+        for (path, page) in self.pages {
+            write_file(output_path, &page_file(&path), page())?;
+        }
+        for (path, file) in self.extra_files {
+            write_file(output_path, &path, file())?;
+        }
+        for (path, file) in self.lists {
+            write_file(output_path, &path, file(vec![]))?;
+        }
+
+        Ok(self.warnings)
+    }
+
+    /// Renders every page and file, followed by the files generated from the rendered output.
+    fn render(self) -> OutputMap {
+        let mut pages: HashMap<String, String> = self
+            .pages
+            .into_iter()
+            .map(|(path, page)| (path, page()))
+            .collect();
+        let header_rules = match &self.content_security_policy {
+            Some(policy) => policy.apply(pages.iter_mut(), &self.header_policies),
+            None => vec![],
+        };
+
+        let mut extra_files: HashMap<String, String> = self
+            .extra_files
+            .into_iter()
+            .map(|(path, file)| (path, file()))
+            .collect();
+        extra_files.extend(
+            self.lists
+                .into_iter()
+                .map(|(path, file)| (path, file(header_rules.clone()))),
+        );
+
+        if let Some(search_index) = self.search_index {
+            let searchable_pages = pages
+                .iter()
+                .filter(|(path, _)| self.searchable.contains(*path))
+                .map(|(path, page)| (path.as_str(), page.as_str()))
+                .collect();
+            extra_files.insert(
+                search_index.file_name.to_owned(),
+                search_index.render(searchable_pages),
+            );
+        }
+
+        let mut output = OutputMap {
+            pages,
+            extra_files,
+            warnings: self.warnings,
+        };
+
+        let post_render_files: Vec<(String, String)> = self
+            .post_render_lists
+            .into_iter()
+            .map(|list| {
+                let content = (list.content_renderer)(&output, &self.manifest);
+                (list.file_name.to_owned(), content)
+            })
+            .collect();
+        output.extra_files.extend(post_render_files);
+
+        output
+    }
 }
 
 /// Mapping of route paths to rendered outputs.
@@ -20,6 +142,57 @@ pub struct OutputMap {
     pub pages: HashMap<String, String>,
     /// Maps additional file paths to their rendered content.
     pub extra_files: HashMap<String, String>,
+    /// Problems found by checks configured with `Level::Warn`.
+    pub warnings: Vec<Diagnostic>,
+}
+
+/// A function that renders a file from the rendered output of the site and the manifest entries of its routes.
+pub type PostRenderListRenderer = Box<dyn FnOnce(&OutputMap, &[ManifestEntry]) -> String>;
+
+/// Configuration for generating a file from the rendered output of the site,
+/// such as a feed or an index that uses the titles of pages.
+///
+/// The renderer receives the rendered pages, including redirect and fallback pages,
+/// along with the files generated by other lists, but not those of other post-render lists.
+/// It also receives the manifest entries of the site's routes, sorted by path and kind,
+/// which give the kind and tags of each page.
+///
+/// # Examples
+///
+/// ```rust
+/// use pagebake::manifest::RouteKind;
+/// use pagebake::render::{PostRenderList, RenderConfig};
+/// use pagebake::{Router, get, redirect};
+///
+/// let router = Router::new()
+///     .route("/", get(|| "<h1>Home</h1>".to_owned()))
+///     .route("/about", get(|| "<h1>About</h1>".to_owned()))
+///     .route("/old-about", redirect("/about"));
+///
+/// let sizes = PostRenderList {
+///     file_name: "sizes.txt",
+///     content_renderer: Box::new(|output, entries| {
+///         entries
+///             .iter()
+///             .filter(|e| e.kind == RouteKind::Page)
+///             .map(|e| format!("{} {}\n", e.path, output.pages[&e.path].len()))
+///             .collect()
+///     }),
+/// };
+///
+/// let config = RenderConfig {
+///     post_render_lists: vec![sizes],
+///     ..Default::default()
+/// };
+///
+/// let output = router.render_to_map(config).unwrap();
+/// assert_eq!(output.extra_files["sizes.txt"], "/ 13\n/about 14\n");
+/// ```
+pub struct PostRenderList {
+    /// The name of the output file.
+    pub file_name: &'static str,
+    /// Function that takes the rendered output and the manifest entries, and returns the file's content.
+    pub content_renderer: PostRenderListRenderer,
 }
 
 /// Configuration options for the rendering process.
@@ -28,15 +201,54 @@ pub struct RenderConfig {
     pub fallback_page_name: String,
     /// When true, chains of redirects will be resolved to their final target.
     pub resolve_redirect_chains: bool,
+    /// The longest chain of redirects that is accepted without a warning.
+    /// Chains are checked whether or not they are resolved. When `None`, chains of any length are accepted.
+    pub max_redirect_chain: Option<usize>,
     /// Optional custom renderer for redirect pages.
     /// When `None`, no redirect pages are included in the output.
+    /// Pattern redirects are expanded into one page for each existing page their target covers.
     pub redirect_page_renderer: Option<RedirectPageRenderer>,
+    /// When true, redirects forward the query string and fragment of the requested URL to their target.
+    /// This applies to redirect pages and to redirect lists of hosts that support it,
+    /// as described for `Redirect::preserve_query`.
+    pub preserve_query: bool,
     /// Configurations for generating files containing redirect mappings.
     /// When empty, no redirect list is included in the output.
     pub redirect_lists: Vec<RedirectList>,
+    /// Configurations for generating files containing the HTTP headers of the site's paths.
+    /// When empty, headers are only sent through redirect lists that include them.
+    pub header_lists: Vec<HeaderList>,
+    /// Names and values of HTTP headers sent for every path of the site, by hosts that support them.
+    /// They are included in header lists before the headers set on routers.
+    pub headers: Vec<(String, String)>,
+    /// Optional configuration for generating a Content-Security-Policy for each page,
+    /// which allows its inline scripts and styles by their hashes.
+    pub content_security_policy: Option<ContentSecurityPolicy>,
     /// Configurations for generating files containing routes (e.g., for sitemaps).
     /// When empty, no route list is included in the output.
     pub route_lists: Vec<RouteList>,
+    /// Configurations for generating files listing every page, redirect and fallback (e.g. for client-side navigation).
+    /// When empty, no route manifest is included in the output.
+    pub route_manifests: Vec<RouteManifest>,
+    /// Optional configuration for generating a `robots.txt` file that references the sitemaps in `route_lists`.
+    pub robots: Option<Robots>,
+    /// Optional configuration for generating a client-side search index from the rendered pages.
+    pub search_index: Option<SearchIndex>,
+    /// Configurations for generating files from the rendered pages and files.
+    /// When empty, no such file is included in the output.
+    pub post_render_lists: Vec<PostRenderList>,
+    /// Paths of files served alongside the rendered site (e.g. `/favicon.ico`), relative to the site root.
+    /// Redirects may point to these files.
+    pub static_files: Vec<String>,
+    /// How redirects to paths that do not exist in the output are reported.
+    pub dangling_redirects: Level,
+    /// How pages, redirects, fallbacks and files that produce the same output file are reported.
+    /// When not denied, only the first origin of each conflict, as named in the diagnostic, is written.
+    pub conflicts: Level,
+    /// The host that the site is deployed to, as set by `HostProfile::render_config`.
+    pub host: Option<HostProfile>,
+    /// How features of the site that `host` or a redirect list cannot serve are reported.
+    pub unsupported_features: Level,
 }
 
 impl Default for RenderConfig {
@@ -45,129 +257,476 @@ impl Default for RenderConfig {
         Self {
             fallback_page_name: "404".to_owned(),
             resolve_redirect_chains: false,
+            max_redirect_chain: Some(3),
             redirect_page_renderer: Some(Redirect::base_redirect_page()),
+            preserve_query: false,
             redirect_lists: vec![],
+            header_lists: vec![],
+            headers: vec![],
+            content_security_policy: None,
             route_lists: vec![],
+            route_manifests: vec![],
+            robots: None,
+            search_index: None,
+            post_render_lists: vec![],
+            static_files: vec![],
+            dangling_redirects: Level::Warn,
+            conflicts: Level::Deny,
+            host: None,
+            unsupported_features: Level::Warn,
         }
     }
 }
 
+/// Writes a rendered file to `path` relative to the output directory, creating its parent directories.
+fn write_file(output_path: &Path, path: &str, content: String) -> io::Result<()> {
+    let export_path = output_path.join(path);
+
+    fs::create_dir_all(export_path.parent().unwrap())?;
+    fs::write(export_path, content)
+}
+
+/// Returns the file path, relative to the output directory, that the page at `path` is written to.
+pub(crate) fn page_file(path: &str) -> String {
+    let page_path = match path.strip_prefix("/").unwrap() {
+        "" => "index",
+        path => path,
+    };
+
+    Path::new(page_path)
+        .with_extension("html")
+        .to_string_lossy()
+        .into_owned()
+}
+
 impl Router {
     /// Prepares a `RenderMap` based on registered routes and a `Router` configuration.
     ///
     /// # Arguments
     ///
     /// * `config` - The rendering configuration options.
-    fn prepare_map(mut self, config: RenderConfig) -> RenderMap {
-        if config.resolve_redirect_chains {
-            self.redirects = self.resolve_redirects();
-        }
+    ///
+    /// # Errors
+    ///
+    /// Returns a `RenderError::Invalid` if redirects form a cycle or any check configured with `Level::Deny` fails.
+    fn prepare_map(mut self, mut config: RenderConfig) -> Result<RenderMap, RenderError> {
+        let mut report = Report::default();
 
-        let redirects: Vec<Redirect> = self
-            .redirects
-            .into_iter()
-            .map(|(source, target)| Redirect { source, target })
+        self.apply_router_tags();
+
+        let route_lists = self.render_route_lists(std::mem::take(&mut config.route_lists));
+        let dropped_files = self.check_conflicts(&config, &route_lists, &mut report);
+        report.add(
+            config.unsupported_features,
+            self.unsupported_features(&config),
+        );
+
+        let tags = SiteTags(
+            self.tags
+                .drain()
+                .map(|(origin, tags)| (origin, tags.into_iter().collect()))
+                .collect(),
+        );
+        let redirects = self.prepare_redirects(&config, &mut report);
+        let header_rules = self.header_rules(std::mem::take(&mut config.headers));
+        let header_policies = csp_header_policies(&config, &header_rules, &mut report);
+
+        let mut routes: Vec<String> = self.routes.keys().cloned().collect();
+        routes.sort();
+        let route_entries: Vec<RouteEntry> = routes
+            .iter()
+            .map(|path| RouteEntry {
+                path: path.clone(),
+                meta: RouteMeta {
+                    tags: tags.of(&Origin::Page(path.clone())).to_vec(),
+                    ..self.route_meta.remove(path).unwrap_or_default()
+                },
+            })
             .collect();
 
-        let routes: Vec<String> = self.routes.keys().map(|s| s.to_owned()).collect();
+        let redirect_pages = config
+            .redirect_page_renderer
+            .take()
+            .map(|renderer| self.add_redirect_pages(renderer, &redirects, &routes));
+        let fallbacks = self.add_fallback_pages(&config.fallback_page_name);
+        let manifest = build_manifest(
+            &route_entries,
+            &redirects,
+            redirect_pages.as_deref(),
+            &fallbacks,
+            &tags,
+        );
 
-        if let Some(renderer) = config.redirect_page_renderer {
-            let renderer = Rc::new(renderer);
+        let written = self.remove_conflicting_pages(
+            &routes,
+            redirect_pages.as_deref().unwrap_or_default(),
+            &fallbacks,
+            &config.fallback_page_name,
+        );
+        // Generated files are only produced by the first of their generators, unless a page produces the same file.
+        let is_kept = |generator: Generator, file: &str| {
+            !written.contains(file.trim_start_matches('/'))
+                && !dropped_files.contains(&Origin::GeneratedFile {
+                    generator,
+                    file: file.to_owned(),
+                })
+        };
+        let pages: Vec<String> = self.routes.keys().cloned().collect();
 
-            for redirect in &redirects {
-                let renderer = Rc::clone(&renderer);
-                let target = redirect.target.to_owned();
+        let mut extra_files = HashMap::<String, FileGenerator>::new();
+        let mut lists = HashMap::<String, ListGenerator>::new();
 
-                self.routes.insert(
-                    redirect.source.to_owned(),
-                    Box::new(move || renderer(&target)),
-                );
+        for (i, list) in config.redirect_lists.into_iter().enumerate() {
+            if !is_kept(Generator::RedirectList(i), list.file_name) {
+                continue;
             }
+
+            let file_name = list.file_name.to_owned();
+            let (generator, unsupported) =
+                redirect_list_generator(list, &redirects, &fallbacks, &header_rules, &pages, &tags);
+            report.add(config.unsupported_features, unsupported);
+            lists.insert(file_name, generator);
         }
 
-        for (mut path, page) in self.fallbacks {
-            if !path.ends_with("/") {
-                path.push('/');
+        for (i, list) in config.header_lists.into_iter().enumerate() {
+            if is_kept(Generator::HeaderList(i), list.file_name) {
+                let merge_rules = header_rule_merger(&list.filter, &header_rules, &pages, &tags);
+                lists.insert(
+                    list.file_name.to_owned(),
+                    Box::new(move |page_rules| (list.content_renderer)(merge_rules(page_rules))),
+                );
             }
-            path.push_str(&config.fallback_page_name);
+        }
 
-            if self.routes.contains_key(&path) {
-                panic!("Overlap with fallback handler. Route `{path}` already exists");
+        for (i, renderer) in config.route_manifests.into_iter().enumerate() {
+            if is_kept(Generator::RouteManifest(i), renderer.file_name) {
+                let manifest = manifest.clone();
+                extra_files.insert(
+                    renderer.file_name.to_owned(),
+                    Box::new(move || (renderer.content_renderer)(manifest)),
+                );
             }
-
-            self.routes.insert(path, page);
         }
 
-        let mut extra_files = HashMap::<String, Box<dyn FnOnce() -> String>>::new();
+        let sitemaps = add_route_lists(route_lists, &is_kept, &mut extra_files);
 
-        // TODO: use references
-        for renderer in config.redirect_lists {
-            let redirects = redirects.clone();
-            extra_files.insert(
-                renderer.file_name.to_owned(),
-                Box::new(move || (renderer.content_renderer)(redirects)),
-            );
+        if let Some(robots) = config.robots {
+            let mut noindex: Vec<String> = route_entries
+                .iter()
+                .filter(|r| r.meta.noindex)
+                .map(|r| r.path.clone())
+                .collect();
+            if robots.disallow_redirects {
+                noindex.extend(redirects.iter().map(|r| r.source.clone()));
+            }
+            if robots.disallow_fallbacks {
+                noindex.extend(fallbacks.iter().map(|f| f.path.clone()));
+            }
+
+            if is_kept(Generator::Robots, Robots::FILE_NAME) {
+                extra_files.insert(
+                    Robots::FILE_NAME.to_owned(),
+                    Box::new(move || robots.render(noindex, sitemaps)),
+                );
+            }
         }
 
-        for renderer in config.route_lists {
-            let mut routes = routes.clone();
-            if renderer.include_redirects {
-                let redirects: Vec<String> = redirects.iter().map(|r| r.source.clone()).collect();
-                routes.extend(redirects);
+        if let Some(search_index) = &mut config.search_index {
+            if let Some(open_search) = search_index.open_search.take() {
+                if is_kept(Generator::OpenSearch, open_search.file_name) {
+                    extra_files.insert(
+                        open_search.file_name.to_owned(),
+                        Box::new(move || open_search.render()),
+                    );
+                }
             }
+        }
+        let search_index = config
+            .search_index
+            .filter(|index| is_kept(Generator::SearchIndex, index.file_name));
+        let searchable = route_entries
+            .iter()
+            .filter(|r| !r.meta.exclude_from_search)
+            .map(|r| r.path.clone())
+            .collect();
 
-            extra_files.insert(
-                renderer.file_name.to_owned(),
-                Box::new(|| (renderer.content_renderer)(routes)),
-            );
+        let post_render_lists: Vec<PostRenderList> = config
+            .post_render_lists
+            .into_iter()
+            .enumerate()
+            .filter(|(i, list)| is_kept(Generator::PostRenderList(*i), list.file_name))
+            .map(|(_, list)| list)
+            .collect();
+
+        for (file_name, content) in config.host.iter().flat_map(HostProfile::files) {
+            if is_kept(Generator::Host, file_name) {
+                extra_files.insert(file_name.to_owned(), Box::new(move || content));
+            }
         }
 
-        RenderMap {
+        let files: Vec<String> = extra_files
+            .keys()
+            .chain(lists.keys())
+            .cloned()
+            .chain(search_index.iter().map(|index| index.file_name.to_owned()))
+            .chain(
+                post_render_lists
+                    .iter()
+                    .map(|list| list.file_name.to_owned()),
+            )
+            .chain(config.static_files)
+            .collect();
+        report.add(
+            config.dangling_redirects,
+            dangling_redirects(&redirects, &pages, &files),
+        );
+
+        Ok(RenderMap {
             pages: self.routes,
             extra_files,
+            lists,
+            content_security_policy: config.content_security_policy,
+            header_policies,
+            warnings: report.finish()?,
+            search_index,
+            searchable,
+            post_render_lists,
+            manifest,
+        })
+    }
+
+    /// Renders the route lists, so that the names of the files they are split into are known before rendering.
+    fn render_route_lists(&self, route_lists: Vec<RouteList>) -> Vec<RenderedRouteList> {
+        let mut sections: Vec<String> = self.sections.iter().cloned().collect();
+        sections.sort();
+
+        route_lists
+            .into_iter()
+            .map(|list| {
+                let (files, index) = list.render_files(&self.route_list_entries(&list), &sections);
+                (list, files, index)
+            })
+            .collect()
+    }
+
+    /// Reports the conflicts of the site, and returns the generated files that are left out
+    /// because another origin of the same file comes first.
+    fn check_conflicts(
+        &self,
+        config: &RenderConfig,
+        route_lists: &[RenderedRouteList],
+        report: &mut Report,
+    ) -> HashSet<Origin> {
+        let route_list_files = route_lists
+            .iter()
+            .map(|(_, files, index)| {
+                files
+                    .iter()
+                    .map(|(name, _)| name.clone())
+                    .chain(index.clone())
+                    .collect()
+            })
+            .collect();
+        let conflicts = self.find_conflicts(config, route_list_files);
+
+        let dropped_files = conflicts
+            .iter()
+            .filter(|c| c.first != c.second && matches!(c.second, Origin::GeneratedFile { .. }))
+            .map(|c| c.second.clone())
+            .collect();
+        report.add(
+            config.conflicts,
+            conflicts.into_iter().map(Diagnostic::Conflict).collect(),
+        );
+
+        dropped_files
+    }
+
+    /// Resolves the relative targets of redirects, checks their chains and, if configured, resolves them.
+    ///
+    /// Returns the redirects sorted in the order hosts should match them.
+    fn prepare_redirects(&mut self, config: &RenderConfig, report: &mut Report) -> Vec<Redirect> {
+        let mut root_relative = vec![];
+        for redirect in self.redirects.values_mut() {
+            if is_relative(&redirect.target) {
+                if !self.nested_redirects.contains(&redirect.source) {
+                    root_relative.push((redirect.source.clone(), redirect.target.clone()));
+                }
+                redirect.target = resolve_relative(&redirect.source, &redirect.target);
+            }
+            redirect.preserve_query |= config.preserve_query;
+        }
+
+        root_relative.sort();
+        report.add(
+            Level::Deny,
+            root_relative
+                .into_iter()
+                .map(|(source, target)| Diagnostic::RelativeRedirect { source, target })
+                .collect(),
+        );
+
+        let (cycles, long_chains) = self.check_redirect_chains(config.max_redirect_chain);
+        report.add(Level::Deny, cycles);
+        report.add(Level::Warn, long_chains);
+
+        if config.resolve_redirect_chains {
+            self.redirects = self.resolve_redirects();
+        }
+
+        let mut redirects: Vec<Redirect> =
+            std::mem::take(&mut self.redirects).into_values().collect();
+        sort_redirects(&mut redirects);
+        redirects
+    }
+
+    /// Returns the header rules of the site sorted by path, with the `global` headers sent for every path
+    /// ahead of those set on routers.
+    fn header_rules(&mut self, global: Vec<(String, String)>) -> Vec<HeaderRule> {
+        let mut headers = std::mem::take(&mut self.headers);
+        if !global.is_empty() {
+            let mut global = global;
+            global.extend(headers.remove(WILDCARD).unwrap_or_default());
+            headers.insert(WILDCARD.to_owned(), global);
+        }
+
+        let mut header_rules: Vec<HeaderRule> = headers
+            .into_iter()
+            .map(|(path, headers)| HeaderRule { path, headers })
+            .collect();
+        header_rules.sort_by(|a, b| a.path.cmp(&b.path));
+        header_rules
+    }
+
+    /// Adds a redirect page for every exact redirect, and for every page covered by the target of a pattern redirect.
+    ///
+    /// Returns the redirects that pages are rendered for.
+    fn add_redirect_pages(
+        &mut self,
+        renderer: RedirectPageRenderer,
+        redirects: &[Redirect],
+        pages: &[String],
+    ) -> Vec<Redirect> {
+        let renderer = Rc::new(renderer);
+        let page_redirects: Vec<Redirect> = redirects
+            .iter()
+            .filter(|r| !r.is_pattern())
+            .cloned()
+            .chain(expand_patterns(redirects, pages))
+            .collect();
+
+        for redirect in &page_redirects {
+            let renderer = Rc::clone(&renderer);
+            let redirect = redirect.clone();
+            self.routes
+                .entry(redirect.source.clone())
+                .or_insert_with(|| Box::new(move || renderer(&redirect)));
         }
+
+        page_redirects
+    }
+
+    /// Adds the fallback pages, named `fallback_page_name` under the prefix they are served for.
+    ///
+    /// Returns the fallbacks sorted by prefix.
+    fn add_fallback_pages(&mut self, fallback_page_name: &str) -> Vec<Fallback> {
+        let mut fallbacks = Vec::<Fallback>::new();
+
+        for (prefix, page) in std::mem::take(&mut self.fallbacks) {
+            let mut path = prefix.clone();
+            if !path.ends_with("/") {
+                path.push('/');
+            }
+            path.push_str(fallback_page_name);
+
+            self.routes.entry(path.clone()).or_insert(page);
+            fallbacks.push(Fallback { prefix, path });
+        }
+
+        fallbacks.sort_by(|a, b| a.prefix.cmp(&b.prefix));
+        fallbacks
+    }
+
+    /// Removes the pages that produce the same output file as another page, redirect page or fallback page,
+    /// keeping the first origin in sorted order, which is the one named first in the conflict.
+    ///
+    /// Returns the files that pages are written to.
+    fn remove_conflicting_pages(
+        &mut self,
+        pages: &[String],
+        redirect_pages: &[Redirect],
+        fallbacks: &[Fallback],
+        fallback_page_name: &str,
+    ) -> HashSet<String> {
+        let redirect_pages: HashSet<&String> = redirect_pages.iter().map(|r| &r.source).collect();
+        let mut origins: Vec<(Origin, String)> = self
+            .routes
+            .keys()
+            .map(|path| {
+                let origin = if pages.binary_search(path).is_ok() {
+                    Origin::Page(path.clone())
+                } else if redirect_pages.contains(path) {
+                    Origin::Redirect(path.clone())
+                } else {
+                    let fallback = fallbacks.iter().find(|f| &f.path == path).unwrap();
+                    Origin::Fallback(fallback.prefix.clone())
+                };
+                (origin, path.clone())
+            })
+            .collect();
+        origins.sort();
+
+        let mut written = HashSet::new();
+        for (origin, path) in origins {
+            if !written.insert(origin.file(fallback_page_name)) {
+                self.routes.remove(&path);
+            }
+        }
+
+        written
     }
 
     /// Renders the site to the specified output directory.
     ///
     /// This function creates necessary directories and writes rendered pages and any additional files (e.g. redirect lists) to disk.
+    /// Each page is written as soon as it is rendered, unless a Content-Security-Policy, a search index or
+    /// post-render lists are configured, which need every rendered page.
     ///
     /// # Arguments
     ///
     /// * `output_path` - The directory where rendered files will be written.
     /// * `config` - The rendering configuration options.
     ///
+    /// Returns the problems found by checks configured with `Level::Warn`.
+    ///
     /// # Errors
     ///
-    /// Returns an `io::Error` if file operations fail.
-    pub fn render(self, output_path: &Path, config: RenderConfig) -> io::Result<()> {
-        let map = self.prepare_map(config);
+    /// Returns a `RenderError::Invalid` if redirects form a cycle or any check configured with `Level::Deny` fails,
+    /// or a `RenderError::Io` if file operations fail.
+    pub fn render(
+        self,
+        output_path: &Path,
+        config: RenderConfig,
+    ) -> Result<Vec<Diagnostic>, RenderError> {
+        let map = self.prepare_map(config)?;
 
         fs::create_dir_all(output_path)?;
 
-        for (path, page) in map.pages {
-            let page_path = match path.strip_prefix("/").unwrap() {
-                "" => "index",
-                path => path,
-            };
-
-            let mut export_path = output_path.to_path_buf();
-            export_path.push(page_path);
-            export_path.set_extension("html");
-
-            fs::create_dir_all(export_path.parent().unwrap())?;
-            fs::write(export_path, page())?;
+        // Pages are only kept in memory when files are generated from them.
+        if !map.is_buffered() {
+            return Ok(map.write(output_path)?);
         }
 
-        for (path, file) in map.extra_files {
-            let mut export_path = output_path.to_path_buf();
-            export_path.push(path);
+        let output = map.render();
 
-            fs::create_dir_all(export_path.parent().unwrap())?;
-            fs::write(export_path, file())?;
+        for (path, page) in output.pages {
+            write_file(output_path, &page_file(&path), page)?;
+        }
+        for (path, file) in output.extra_files {
+            write_file(output_path, &path, file)?;
         }
 
-        Ok(())
+        Ok(output.warnings)
     }
 
     /// Renders the site into an in-memory map.
@@ -175,20 +734,290 @@ impl Router {
     /// Returns an `OutputMap` where:
     /// - Keys represent the file paths (relative to the site root)
     /// - Values are the rendered content for each HTML page and and any additional files (e.g. redirect lists).
-    pub fn render_to_map(self, config: RenderConfig) -> OutputMap {
-        let map = self.prepare_map(config);
+    ///
+    /// # Errors
+    ///
+    /// Returns a `RenderError::Invalid` if redirects form a cycle or any check configured with `Level::Deny` fails.
+    pub fn render_to_map(self, config: RenderConfig) -> Result<OutputMap, RenderError> {
+        Ok(self.prepare_map(config)?.render())
+    }
+}
 
-        OutputMap {
-            pages: map
-                .pages
-                .into_iter()
-                .map(|(path, page)| (path, page()))
-                .collect(),
-            extra_files: map
-                .extra_files
-                .into_iter()
-                .map(|(path, file)| (path, file()))
-                .collect(),
+/// Tags of the pages, redirects and fallbacks of a site.
+struct SiteTags(HashMap<Origin, Vec<String>>);
+
+impl SiteTags {
+    /// Returns the tags of `origin`.
+    fn of(&self, origin: &Origin) -> &[String] {
+        self.0.get(origin).map_or(&[], Vec::as_slice)
+    }
+
+    /// Returns the tags of the page or redirect at `path`.
+    fn of_path(&self, path: &str) -> &[String] {
+        match self.of(&Origin::Page(path.to_owned())) {
+            [] => self.of(&Origin::Redirect(path.to_owned())),
+            tags => tags,
+        }
+    }
+}
+
+/// Returns the `Content-Security-Policy` headers set for single pages, which are merged into their generated policies.
+///
+/// Policies set for patterns, which would be sent along with the generated ones, are reported,
+/// as is a policy delivered as a header when no header list or redirect list sends headers.
+fn csp_header_policies(
+    config: &RenderConfig,
+    header_rules: &[HeaderRule],
+    report: &mut Report,
+) -> HashMap<String, String> {
+    let mut header_policies = HashMap::new();
+    if config
+        .content_security_policy
+        .as_ref()
+        .is_none_or(|policy| policy.output != CspOutput::Header)
+    {
+        return header_policies;
+    }
+
+    let mut conflicts = vec![];
+    for rule in header_rules {
+        let Some((_, value)) = rule
+            .headers
+            .iter()
+            .find(|(name, _)| name.eq_ignore_ascii_case(CSP_HEADER))
+        else {
+            continue;
+        };
+        if rule.is_pattern() {
+            conflicts.push(Diagnostic::CspHeaderConflict {
+                path: rule.path.clone(),
+            });
+        } else {
+            header_policies.insert(rule.path.clone(), value.clone());
         }
     }
+    report.add(Level::Warn, conflicts);
+
+    let sends_headers = !config.header_lists.is_empty()
+        || config
+            .redirect_lists
+            .iter()
+            .any(|list| list.includes_headers);
+    if !sends_headers
+        && config
+            .host
+            .as_ref()
+            .is_none_or(|host| host.supports_headers())
+    {
+        report.add(
+            config.unsupported_features,
+            vec![Diagnostic::CspWithoutHeaderList],
+        );
+    }
+
+    header_policies
+}
+
+/// Builds the manifest entries of the pages, redirects and fallbacks of a site, sorted by path and kind.
+///
+/// When `redirect_pages` are rendered, their redirects are listed with their file, including the pages
+/// that pattern redirects are expanded into, which take the tags of their pattern.
+fn build_manifest(
+    route_entries: &[RouteEntry],
+    redirects: &[Redirect],
+    redirect_pages: Option<&[Redirect]>,
+    fallbacks: &[Fallback],
+    tags: &SiteTags,
+) -> Vec<ManifestEntry> {
+    let sources: HashSet<&str> = redirects.iter().map(|r| r.source.as_str()).collect();
+    let redirect_tags = |source: &str| {
+        let rule = if sources.contains(source) {
+            Some(source)
+        } else {
+            redirects
+                .iter()
+                .map(|r| r.source.as_str())
+                .find(|rule| pattern_prefix(rule).is_some_and(|prefix| source.starts_with(prefix)))
+        };
+        rule.map_or(&[][..], |rule| tags.of(&Origin::Redirect(rule.to_owned())))
+            .to_vec()
+    };
+
+    let mut manifest: Vec<ManifestEntry> = route_entries
+        .iter()
+        .map(|r| ManifestEntry {
+            path: r.path.clone(),
+            kind: RouteKind::Page,
+            file: Some(page_file(&r.path)),
+            target: None,
+            status: None,
+            scope: None,
+            tags: r.meta.tags.clone(),
+        })
+        .collect();
+
+    let patterns = redirects.iter().filter(|r| r.is_pattern());
+    let exact: Vec<&Redirect> = match redirect_pages {
+        Some(redirect_pages) => redirect_pages.iter().collect(),
+        None => redirects.iter().filter(|r| !r.is_pattern()).collect(),
+    };
+    manifest.extend(
+        patterns
+            .map(|r| (r, None))
+            .chain(
+                exact
+                    .into_iter()
+                    .map(|r| (r, redirect_pages.map(|_| page_file(&r.source)))),
+            )
+            .map(|(r, file)| ManifestEntry {
+                path: r.source.clone(),
+                kind: RouteKind::Redirect,
+                file,
+                target: Some(r.target.clone()),
+                status: Some(r.status),
+                scope: None,
+                tags: redirect_tags(&r.source),
+            }),
+    );
+
+    manifest.extend(fallbacks.iter().map(|f| ManifestEntry {
+        path: f.path.clone(),
+        kind: RouteKind::Fallback,
+        file: Some(page_file(&f.path)),
+        target: None,
+        status: None,
+        scope: Some(f.prefix.clone()),
+        tags: tags.of(&Origin::Fallback(f.prefix.clone())).to_vec(),
+    }));
+
+    manifest.sort_by(|a, b| a.path.cmp(&b.path).then(a.kind.cmp(&b.kind)));
+    manifest
+}
+
+/// Prepares the generator of a redirect list from the redirects, fallbacks, header rules and `pages`
+/// selected by its filter.
+///
+/// Returns the generator along with the redirects that the list's check left out.
+fn redirect_list_generator(
+    list: RedirectList,
+    redirects: &[Redirect],
+    fallbacks: &[Fallback],
+    header_rules: &[HeaderRule],
+    pages: &[String],
+    tags: &SiteTags,
+) -> (ListGenerator, Vec<Diagnostic>) {
+    let filter = &list.filter;
+    let mut unsupported = vec![];
+
+    let redirects: Vec<Redirect> = redirects
+        .iter()
+        .filter(|r| filter.matches(&r.source, tags.of(&Origin::Redirect(r.source.clone()))))
+        .filter(|r| match list.check.map_or(Ok(()), |check| check(r)) {
+            Ok(()) => true,
+            Err(reason) => {
+                unsupported.push(Diagnostic::UnsupportedRedirect {
+                    file: list.file_name.to_owned(),
+                    source: r.source.clone(),
+                    reason,
+                });
+                false
+            }
+        })
+        .cloned()
+        .collect();
+    let fallbacks: Vec<Fallback> = fallbacks
+        .iter()
+        .filter(|f| filter.matches(&f.prefix, tags.of(&Origin::Fallback(f.prefix.clone()))))
+        .cloned()
+        .collect();
+    let merge_rules = header_rule_merger(filter, header_rules, pages, tags);
+
+    let generator: ListGenerator = Box::new(move |page_rules: Vec<HeaderRule>| {
+        (list.content_renderer)(RedirectListInput {
+            redirects,
+            fallbacks,
+            headers: merge_rules(page_rules),
+        })
+    });
+
+    (generator, unsupported)
+}
+
+/// Selects the header rules and `pages` matched by `filter`, and returns a function that merges
+/// the selected rules with the rules generated for the selected pages, sorted by path.
+fn header_rule_merger(
+    filter: &RouteFilter,
+    header_rules: &[HeaderRule],
+    pages: &[String],
+    tags: &SiteTags,
+) -> impl FnOnce(Vec<HeaderRule>) -> Vec<HeaderRule> + 'static {
+    let mut header_rules: Vec<HeaderRule> = header_rules
+        .iter()
+        .filter(|rule| {
+            let path = pattern_prefix(&rule.path).unwrap_or(&rule.path);
+            filter.matches(path, tags.of_path(&rule.path))
+        })
+        .cloned()
+        .collect();
+    let pages: HashSet<String> = pages
+        .iter()
+        .filter(|path| filter.matches(path, tags.of_path(path)))
+        .cloned()
+        .collect();
+
+    move |page_rules: Vec<HeaderRule>| {
+        for rule in page_rules.into_iter().filter(|r| pages.contains(&r.path)) {
+            match header_rules.iter_mut().find(|r| r.path == rule.path) {
+                Some(existing) => {
+                    // The generated policy already includes a policy set for the page by hand.
+                    existing
+                        .headers
+                        .retain(|(name, _)| !name.eq_ignore_ascii_case(CSP_HEADER));
+                    existing.headers.extend(rule.headers);
+                }
+                None => header_rules.push(rule),
+            }
+        }
+        header_rules.sort_by(|a, b| a.path.cmp(&b.path));
+        header_rules
+    }
+}
+
+/// Adds the files of the route lists that are kept to `extra_files`.
+///
+/// Returns the names of the sitemaps among them, which are the index files of sitemaps that have one.
+fn add_route_lists(
+    route_lists: Vec<RenderedRouteList>,
+    is_kept: &impl Fn(Generator, &str) -> bool,
+    extra_files: &mut HashMap<String, FileGenerator>,
+) -> Vec<String> {
+    let mut sitemaps = vec![];
+
+    for (i, (list, files, index_file_name)) in route_lists.into_iter().enumerate() {
+        let is_kept = |file_name: &str| is_kept(Generator::RouteList(i), file_name);
+
+        let file_names: Vec<String> = files.iter().map(|(name, _)| name.clone()).collect();
+        for (file_name, content) in files.into_iter().filter(|(name, _)| is_kept(name)) {
+            extra_files.insert(file_name, Box::new(move || content));
+        }
+
+        let (Some(index), Some(index_file_name)) = (list.index, index_file_name) else {
+            if list.sitemap && is_kept(list.file_name) {
+                sitemaps.push(list.file_name.to_owned());
+            }
+            continue;
+        };
+
+        if is_kept(&index_file_name) {
+            if list.sitemap {
+                sitemaps.push(index_file_name.clone());
+            }
+            extra_files.insert(
+                index_file_name,
+                Box::new(move || (index.content_renderer)(file_names)),
+            );
+        }
+    }
+
+    sitemaps
 }
@@ -0,0 +1,109 @@
+use crate::{escape, redirects::pattern_prefix};
+
+/// Configuration for generating a `robots.txt` file.
+///
+/// The file references every route list marked as a sitemap, and disallows crawling of pages
+/// whose metadata sets `noindex`. Disallow rules of paths are anchored with a `$`, so that they only
+/// match the path itself, while paths ending in `/*`, such as pattern redirects, match every path under
+/// their prefix. The root of the site is only disallowed as a whole when `disallow` asks for it.
+///
+/// # Examples
+///
+/// ```rust
+/// use pagebake::render::RenderConfig;
+/// use pagebake::robots::Robots;
+/// use pagebake::routes::{RouteList, RouteMeta};
+/// use pagebake::{Router, get};
+///
+/// let router = Router::new()
+///     .route("/", get(|| "<h1>Home</h1>".to_owned()))
+///     .route(
+///         "/drafts",
+///         get(|| "<h1>Drafts</h1>".to_owned()).with_meta(RouteMeta {
+///             noindex: true,
+///             ..Default::default()
+///         }),
+///     );
+///
+/// let config = RenderConfig {
+///     route_lists: vec![RouteList::sitemap("https://example.com".to_owned())],
+///     robots: Some(Robots::new("https://example.com".to_owned())),
+///     ..Default::default()
+/// };
+///
+/// let output = router.render_to_map(config).unwrap();
+/// assert!(output.extra_files["robots.txt"].contains("Disallow: /drafts$\n"));
+/// assert!(output.extra_files["robots.txt"].contains("Sitemap: https://example.com/sitemap.xml\n"));
+/// ```
+pub struct Robots {
+    /// The origin of the site, used to reference sitemaps with absolute URLs. A trailing `/` is ignored.
+    pub origin_url: String,
+    /// Additional paths that crawlers should not visit. A path ending in `/*` disallows every path under its prefix,
+    /// so `/*` disallows the whole site.
+    pub disallow: Vec<String>,
+    /// Whether redirects are disallowed.
+    pub disallow_redirects: bool,
+    /// Whether fallback pages are disallowed.
+    pub disallow_fallbacks: bool,
+}
+
+impl Robots {
+    /// The name of the generated file.
+    pub const FILE_NAME: &'static str = "robots.txt";
+
+    /// Creates a `Robots` configuration that disallows fallback pages.
+    pub fn new(origin_url: String) -> Self {
+        Self {
+            origin_url,
+            disallow: vec![],
+            disallow_redirects: false,
+            disallow_fallbacks: true,
+        }
+    }
+
+    /// Renders the file, given the disallowed paths of the site and the file names of its sitemaps.
+    ///
+    /// Disallowed paths of the site that would match every path, such as a pattern redirect from `/*`, are left out.
+    pub(crate) fn render(self, disallow: Vec<String>, sitemaps: Vec<String>) -> String {
+        let mut rules: Vec<String> = disallow
+            .iter()
+            .filter(|path| pattern_prefix(path) != Some("/"))
+            .chain(&self.disallow)
+            .map(|path| disallow_rule(path))
+            .collect();
+        rules.sort();
+        rules.dedup();
+
+        let mut content = String::from("User-agent: *\n");
+        if rules.is_empty() {
+            content.push_str("Disallow:\n");
+        }
+        for rule in rules {
+            content.push_str(&format!("Disallow: {rule}\n"));
+        }
+
+        if !sitemaps.is_empty() {
+            content.push('\n');
+        }
+        for sitemap in sitemaps {
+            content.push_str(&format!(
+                "Sitemap: {}/{}\n",
+                escape::origin(&self.origin_url),
+                escape::url(&sitemap)
+            ));
+        }
+
+        content
+    }
+}
+
+/// Formats the value of a `Disallow` rule: the prefix of a path ending in `/*`, or an exact path anchored with a `$`.
+fn disallow_rule(path: &str) -> String {
+    match pattern_prefix(path) {
+        Some(prefix) => escape::url(prefix),
+        None => format!(
+            "{}$",
+            escape::url(path).replace('*', "%2A").replace('$', "%24")
+        ),
+    }
+}
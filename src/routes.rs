@@ -1,6 +1,165 @@
-/// A function that renders a list of routes, given a vector of routes.
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    path::Path,
+};
+
+use crate::{
+    diagnostics::Origin,
+    escape,
+    feeds::{check_feed_item, FeedItem},
+    headers::check_header,
+    redirects::{is_external_url, pattern_prefix},
+    Router,
+};
+
+/// A function that renders a list of routes, given a vector of route entries.
 /// Route lists can be used to generate sitemaps.
-pub type RouteListRenderer = Box<dyn FnOnce(Vec<String>) -> String>;
+/// The function is called once for each file when routes are split into several files.
+pub type RouteListRenderer = Box<dyn Fn(Vec<RouteEntry>) -> String>;
+
+/// A function that renders an index of route list files, given their paths relative to the output directory.
+pub type RouteListIndexRenderer = Box<dyn FnOnce(Vec<String>) -> String>;
+
+/// Metadata attached to a page when it is registered.
+///
+/// # Examples
+///
+/// ```rust
+/// use pagebake::routes::{ChangeFreq, RouteMeta};
+///
+/// RouteMeta {
+///     changefreq: Some(ChangeFreq::Daily),
+///     ..Default::default()
+/// };
+/// ```
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RouteMeta {
+    /// Date of the last modification of the page in W3C Datetime format, such as `2024-05-01`.
+    pub lastmod: Option<String>,
+    /// How frequently the page is likely to change.
+    pub changefreq: Option<ChangeFreq>,
+    /// Priority of the page relative to other pages of the site, from `0.0` to `1.0`.
+    /// It is written to sitemaps as given.
+    pub priority: Option<f32>,
+    /// Whether the page should not be indexed by search engines.
+    /// Such pages are left out of sitemaps and disallowed in `robots.txt`.
+    pub noindex: bool,
+    /// Whether the page is left out of the search index.
+    pub exclude_from_search: bool,
+    /// Feed metadata, which makes the page an item of feeds.
+    pub feed: Option<FeedItem>,
+    /// Paths or absolute URLs of the images on the page, listed in sitemaps as `<image:image>` entries.
+    pub images: Vec<String>,
+    /// Versions of the page in other languages, listed in sitemaps as `<xhtml:link rel="alternate">` entries.
+    pub alternates: Vec<Alternate>,
+    /// Tags of the page, which can be used to filter route lists.
+    /// Route list renderers also receive the tags of the routers that the page belongs to.
+    pub tags: Vec<String>,
+    /// Names and values of HTTP headers sent with the page, by hosts that support them.
+    /// Headers of the routers that the page belongs to are not included.
+    pub headers: Vec<(String, String)>,
+}
+
+/// A version of a page in another language or region.
+///
+/// # Examples
+///
+/// ```rust
+/// use pagebake::routes::{Alternate, RouteMeta};
+/// use pagebake::{Router, get};
+///
+/// let alternates = vec![
+///     Alternate {
+///         hreflang: "en".to_owned(),
+///         href: "/en/about".to_owned(),
+///     },
+///     Alternate {
+///         hreflang: "de".to_owned(),
+///         href: "/de/about".to_owned(),
+///     },
+/// ];
+///
+/// let router = Router::new()
+///     .route(
+///         "/en/about",
+///         get(|| "<h1>About</h1>".to_owned()).with_meta(RouteMeta {
+///             alternates: alternates.clone(),
+///             images: vec!["/images/team.jpg".to_owned()],
+///             ..Default::default()
+///         }),
+///     )
+///     .route(
+///         "/de/about",
+///         get(|| "<h1>Über uns</h1>".to_owned()).with_meta(RouteMeta {
+///             alternates,
+///             ..Default::default()
+///         }),
+///     );
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Alternate {
+    /// The language code of the version, optionally with a region, such as `de` or `en-GB`,
+    /// or `x-default` for the version shown to other languages.
+    pub hreflang: String,
+    /// The path or absolute URL of the version.
+    pub href: String,
+}
+
+/// Checks that the metadata of a page is valid, returning a description of the problem otherwise.
+pub(crate) fn check_meta(meta: &RouteMeta) -> Result<(), String> {
+    if let Some(priority) = meta.priority {
+        if !(0.0..=1.0).contains(&priority) {
+            return Err(format!(
+                "Sitemap priority must be between 0.0 and 1.0. Found `{priority}`"
+            ));
+        }
+    }
+
+    for (name, value) in &meta.headers {
+        check_header(name, value)?;
+    }
+
+    match &meta.feed {
+        Some(item) => check_feed_item(item),
+        None => Ok(()),
+    }
+}
+
+/// How frequently a page is likely to change, as defined by the sitemap protocol.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChangeFreq {
+    Always,
+    Hourly,
+    Daily,
+    Weekly,
+    Monthly,
+    Yearly,
+    Never,
+}
+
+impl ChangeFreq {
+    /// Returns the value used for the `<changefreq>` element of sitemaps.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ChangeFreq::Always => "always",
+            ChangeFreq::Hourly => "hourly",
+            ChangeFreq::Daily => "daily",
+            ChangeFreq::Weekly => "weekly",
+            ChangeFreq::Monthly => "monthly",
+            ChangeFreq::Yearly => "yearly",
+            ChangeFreq::Never => "never",
+        }
+    }
+}
+
+/// A route passed to route list renderers.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RouteEntry {
+    /// The route path, such as `/blog/post`.
+    pub path: String,
+    /// The metadata of the page. Redirects have default metadata.
+    pub meta: RouteMeta,
+}
 
 /// Configuration for generating a route list file.
 pub struct RouteList {
@@ -10,24 +169,151 @@ pub struct RouteList {
     pub content_renderer: RouteListRenderer,
     /// Whether to include redirect endpoints to the routes.
     pub include_redirects: bool,
+    /// Filter selecting the routes included in the list.
+    pub filter: RouteFilter,
+    /// Whether the file is a sitemap.
+    /// Sitemaps leave out pages that should not be indexed, and are referenced in `robots.txt`.
+    pub sitemap: bool,
+    /// Configuration for splitting the routes into several files listed by an index file.
+    /// When `None`, all routes are written to a single file.
+    pub index: Option<RouteListIndex>,
+}
+
+/// Filter selecting the pages, redirects and fallbacks included in a route or redirect list,
+/// by their tags and path prefixes.
+///
+/// An entry is included if it has one of the included tags and is under one of the included prefixes,
+/// unless it has an excluded tag or is under an excluded prefix. Empty inclusions include every entry.
+/// Fallbacks are matched by the prefix they are served for.
+///
+/// # Examples
+///
+/// ```rust
+/// use pagebake::routes::{RouteFilter, RouteList};
+///
+/// let docs_sitemap = RouteList {
+///     file_name: "docs/sitemap.xml",
+///     filter: RouteFilter::default()
+///         .include_prefix("/docs")
+///         .exclude_tag("utility"),
+///     ..RouteList::sitemap("https://example.com".to_owned())
+/// };
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RouteFilter {
+    /// Tags of which entries must have at least one. When empty, entries with any tags are included.
+    pub include_tags: Vec<String>,
+    /// Tags of which entries must have none.
+    pub exclude_tags: Vec<String>,
+    /// Prefixes of which entries must be under at least one. When empty, entries with any path are included.
+    pub include_prefixes: Vec<String>,
+    /// Prefixes of which entries must be under none.
+    pub exclude_prefixes: Vec<String>,
+}
+
+impl RouteFilter {
+    /// Includes only entries with the given tag, or with another included tag.
+    pub fn include_tag(mut self, tag: &str) -> Self {
+        self.include_tags.push(tag.to_owned());
+        self
+    }
+
+    /// Excludes entries with the given tag.
+    pub fn exclude_tag(mut self, tag: &str) -> Self {
+        self.exclude_tags.push(tag.to_owned());
+        self
+    }
+
+    /// Includes only entries under the given prefix, such as `/docs`, or under another included prefix.
+    pub fn include_prefix(mut self, prefix: &str) -> Self {
+        self.include_prefixes.push(prefix.to_owned());
+        self
+    }
+
+    /// Excludes entries under the given prefix.
+    pub fn exclude_prefix(mut self, prefix: &str) -> Self {
+        self.exclude_prefixes.push(prefix.to_owned());
+        self
+    }
+
+    /// Returns `true` if an entry with the given path and tags is selected by the filter.
+    pub fn matches(&self, path: &str, tags: &[String]) -> bool {
+        let under = |prefix: &String| {
+            let prefix = prefix.trim_end_matches('/');
+            path == prefix || path.starts_with(&format!("{prefix}/"))
+        };
+
+        (self.include_tags.is_empty() || self.include_tags.iter().any(|t| tags.contains(t)))
+            && !self.exclude_tags.iter().any(|t| tags.contains(t))
+            && (self.include_prefixes.is_empty() || self.include_prefixes.iter().any(under))
+            && !self.exclude_prefixes.iter().any(under)
+    }
+}
+
+/// Configuration for splitting a route list into several files and generating an index of them.
+///
+/// Routes are split when there are more than `chunk_size` of them, when a file would be larger than
+/// `max_file_size`, or when `by_section` is set and the site has nested routers. The files are named after the route list, such as `sitemap-1.xml`,
+/// `sitemap-2.xml` and, for a router nested under `/blog`, `sitemap-blog-1.xml`,
+/// and the index is named like `sitemap_index.xml`.
+/// Otherwise, a single file with the name of the route list is written and no index is generated.
+pub struct RouteListIndex {
+    /// Function that takes the paths of the route list files and returns the index's content.
+    pub content_renderer: RouteListIndexRenderer,
+    /// The largest number of routes written to a single file.
+    pub chunk_size: usize,
+    /// The largest size of a single file in bytes. Files that are larger are split further.
+    pub max_file_size: Option<usize>,
+    /// Whether routes of each nested router are written to files of their own.
+    pub by_section: bool,
 }
 
 impl RouteList {
     /// Creates a `RouteList` configuration for sitemaps.
     ///
     /// The generated file will be named `sitemap.xml` and contain the all non-redirect routes arranged as a sitemap.
+    /// The `<lastmod>`, `<changefreq>` and `<priority>` elements are included for routes whose metadata sets them.
+    /// Paths are percent-encoded where needed, and a trailing `/` of `origin_url` is ignored.
+    ///
+    /// The images and language alternates of routes are included with the
+    /// [image](https://developers.google.com/search/docs/crawling-indexing/sitemaps/image-sitemaps) and
+    /// [hreflang](https://developers.google.com/search/docs/specialty/international/localized-versions#sitemap)
+    /// extensions, whose namespaces are only declared in files that use them.
+    ///
+    /// Sites with more than 50,000 routes or 50 MB of sitemap, the limits of the sitemap protocol,
+    /// are split into `sitemap-1.xml`, `sitemap-2.xml` and so on, which are listed in `sitemap_index.xml`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use pagebake::routes::RouteList;
+    ///
+    /// let sitemap = RouteList::sitemap("https://example.com".to_owned())
+    ///     .chunk_size(1000)
+    ///     .split_by_section();
+    /// ```
     pub fn sitemap(origin_url: String) -> Self {
+        let index_origin_url = origin_url.clone();
+
         RouteList {
             file_name: "sitemap.xml",
-            content_renderer: Box::new(move |routes: Vec<String>| {
+            content_renderer: Box::new(move |routes: Vec<RouteEntry>| {
                 let mut content = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
-                content
-                    .push_str("<urlset xmlns=\"http://www.sitemaps.org/schemas/sitemap/0.9\">\n");
+                content.push_str("<urlset xmlns=\"http://www.sitemaps.org/schemas/sitemap/0.9\"");
+                if routes.iter().any(|r| !r.meta.images.is_empty()) {
+                    content.push_str(
+                        "\n  xmlns:image=\"http://www.google.com/schemas/sitemap-image/1.1\"",
+                    );
+                }
+                if routes.iter().any(|r| !r.meta.alternates.is_empty()) {
+                    content.push_str("\n  xmlns:xhtml=\"http://www.w3.org/1999/xhtml\"");
+                }
+                content.push_str(">\n");
 
                 content.push_str(
                     &routes
                         .iter()
-                        .map(|r| format!("  <url>\n    <loc>{}{}</loc>\n  </url>", &origin_url, r))
+                        .map(|r| sitemap_url(&origin_url, r))
                         .collect::<Vec<String>>()
                         .join("\n"),
                 );
@@ -36,6 +322,281 @@ impl RouteList {
                 content
             }),
             include_redirects: false,
+            filter: RouteFilter::default(),
+            sitemap: true,
+            index: Some(RouteListIndex {
+                content_renderer: Box::new(move |files: Vec<String>| {
+                    let mut content = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+                    content.push_str(
+                        "<sitemapindex xmlns=\"http://www.sitemaps.org/schemas/sitemap/0.9\">\n",
+                    );
+
+                    content.push_str(
+                        &files
+                            .iter()
+                            .map(|f| {
+                                let url = format!(
+                                    "{}/{}",
+                                    escape::origin(&index_origin_url),
+                                    escape::url(f)
+                                );
+                                format!(
+                                    "  <sitemap>\n    <loc>{}</loc>\n  </sitemap>",
+                                    escape::xml(&url)
+                                )
+                            })
+                            .collect::<Vec<String>>()
+                            .join("\n"),
+                    );
+
+                    content.push_str("\n</sitemapindex>");
+                    content
+                }),
+                chunk_size: 50_000,
+                max_file_size: Some(50 * 1024 * 1024),
+                by_section: false,
+            }),
+        }
+    }
+
+    /// Sets the largest number of routes written to a single file before the list is split.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the route list has no index, or if `chunk_size` is zero.
+    pub fn chunk_size(mut self, chunk_size: usize) -> Self {
+        if chunk_size == 0 {
+            panic!("Chunk size of route lists must not be zero");
+        }
+        let Some(index) = &mut self.index else {
+            panic!("Only route lists with an index can be split");
+        };
+
+        index.chunk_size = chunk_size;
+        self
+    }
+
+    /// Writes the routes of each nested router to files of their own, listed in the index.
+    ///
+    /// Routes belong to the router nested under the longest matching prefix.
+    /// Routes that are not under any nested router are written to the files of the site root.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the route list has no index.
+    pub fn split_by_section(mut self) -> Self {
+        let Some(index) = &mut self.index else {
+            panic!("Only route lists with an index can be split");
+        };
+
+        index.by_section = true;
+        self
+    }
+
+    /// Returns the name of the index file, if the route list can be split.
+    pub(crate) fn index_file_name(&self) -> Option<String> {
+        self.index
+            .as_ref()
+            .map(|_| self.suffixed_file_name("_index"))
+    }
+
+    /// Returns the name of the route list's file with `suffix` appended to its stem, such as `sitemap-1.xml`.
+    fn suffixed_file_name(&self, suffix: &str) -> String {
+        let path = Path::new(self.file_name);
+        let stem = path.file_stem().unwrap().to_string_lossy();
+        let extension = path
+            .extension()
+            .map_or("".to_owned(), |e| format!(".{}", e.to_string_lossy()));
+
+        path.with_file_name(format!("{stem}{suffix}{extension}"))
+            .to_string_lossy()
+            .into_owned()
+    }
+
+    /// Renders the routes into the files they are written to, along with the name of the index file
+    /// if the routes are split.
+    ///
+    /// `sections` are the prefixes that routers were nested under.
+    pub(crate) fn render_files(
+        &self,
+        routes: &[RouteEntry],
+        sections: &[String],
+    ) -> (Vec<(String, String)>, Option<String>) {
+        let single_file = |routes: &[RouteEntry]| {
+            vec![(
+                self.file_name.to_owned(),
+                (self.content_renderer)(routes.to_vec()),
+            )]
+        };
+        let Some(index) = &self.index else {
+            return (single_file(routes), None);
+        };
+
+        let mut groups = BTreeMap::<&str, Vec<RouteEntry>>::new();
+        for route in routes {
+            let section = if index.by_section {
+                sections
+                    .iter()
+                    .filter(|s| route.path == **s || route.path.starts_with(&format!("{s}/")))
+                    .max_by_key(|s| s.len())
+                    .map_or("", |s| s.as_str())
+            } else {
+                ""
+            };
+            groups.entry(section).or_default().push(route.clone());
         }
+
+        let mut parts = vec![];
+        for (section, routes) in groups {
+            let mut contents = vec![];
+            for chunk in routes.chunks(index.chunk_size) {
+                self.render_chunk(chunk, index.max_file_size, &mut contents);
+            }
+            parts.push((section, contents));
+        }
+
+        if parts.len() <= 1
+            && parts
+                .first()
+                .is_none_or(|(_, contents)| contents.len() == 1)
+        {
+            return match parts.pop() {
+                Some((_, mut contents)) => {
+                    (vec![(self.file_name.to_owned(), contents.remove(0))], None)
+                }
+                None => (single_file(&[]), None),
+            };
+        }
+
+        let mut files = vec![];
+        for (section, contents) in parts {
+            let section = match section.trim_start_matches('/') {
+                "" => "".to_owned(),
+                section => format!("{}-", section.replace('/', "-")),
+            };
+
+            for (i, content) in contents.into_iter().enumerate() {
+                files.push((
+                    self.suffixed_file_name(&format!("-{section}{}", i + 1)),
+                    content,
+                ));
+            }
+        }
+
+        (files, self.index_file_name())
+    }
+
+    /// Renders a chunk of routes, halving it until each part is at most `max_file_size` bytes long.
+    fn render_chunk(
+        &self,
+        routes: &[RouteEntry],
+        max_file_size: Option<usize>,
+        contents: &mut Vec<String>,
+    ) {
+        let content = (self.content_renderer)(routes.to_vec());
+
+        if routes.len() > 1 && max_file_size.is_some_and(|max| content.len() > max) {
+            let (first, second) = routes.split_at(routes.len() / 2);
+            self.render_chunk(first, max_file_size, contents);
+            self.render_chunk(second, max_file_size, contents);
+        } else {
+            contents.push(content);
+        }
+    }
+}
+
+impl Router {
+    /// Returns the routes included in a route list: its pages sorted by path, followed by its redirects.
+    pub(crate) fn route_list_entries(&self, list: &RouteList) -> Vec<RouteEntry> {
+        let tags = |origin: Origin| -> Vec<String> {
+            let tags: BTreeSet<&String> = self
+                .tags
+                .get(&origin)
+                .into_iter()
+                .flatten()
+                .chain(&self.router_tags)
+                .collect();
+            tags.into_iter().cloned().collect()
+        };
+
+        let mut pages: Vec<&String> = self.routes.keys().collect();
+        pages.sort();
+        let mut routes: Vec<RouteEntry> = pages
+            .into_iter()
+            .map(|path| RouteEntry {
+                path: path.clone(),
+                meta: RouteMeta {
+                    tags: tags(Origin::Page(path.clone())),
+                    ..self.route_meta.get(path).cloned().unwrap_or_default()
+                },
+            })
+            .filter(|r| !(list.sitemap && r.meta.noindex))
+            .collect();
+
+        if list.include_redirects {
+            let mut redirects: Vec<&String> = self
+                .redirects
+                .keys()
+                .filter(|source| pattern_prefix(source).is_none())
+                .collect();
+            redirects.sort();
+            routes.extend(redirects.into_iter().map(|source| RouteEntry {
+                path: source.clone(),
+                meta: RouteMeta {
+                    tags: tags(Origin::Redirect(source.clone())),
+                    ..Default::default()
+                },
+            }));
+        }
+
+        routes.retain(|r| list.filter.matches(&r.path, &r.meta.tags));
+        routes
+    }
+}
+
+/// Formats the `<url>` element of a sitemap for a route.
+fn sitemap_url(origin_url: &str, route: &RouteEntry) -> String {
+    let loc = absolute_url(origin_url, &route.path);
+    let mut url = format!("  <url>\n    <loc>{}</loc>\n", escape::xml(&loc));
+
+    if let Some(lastmod) = &route.meta.lastmod {
+        url.push_str(&format!(
+            "    <lastmod>{}</lastmod>\n",
+            escape::xml(lastmod)
+        ));
+    }
+    if let Some(changefreq) = route.meta.changefreq {
+        url.push_str(&format!(
+            "    <changefreq>{}</changefreq>\n",
+            changefreq.as_str()
+        ));
+    }
+    if let Some(priority) = route.meta.priority {
+        url.push_str(&format!("    <priority>{priority}</priority>\n"));
+    }
+    for alternate in &route.meta.alternates {
+        url.push_str(&format!(
+            "    <xhtml:link rel=\"alternate\" hreflang=\"{}\" href=\"{}\"/>\n",
+            escape::xml(&alternate.hreflang),
+            escape::xml(&absolute_url(origin_url, &alternate.href))
+        ));
+    }
+    for image in &route.meta.images {
+        url.push_str(&format!(
+            "    <image:image>\n      <image:loc>{}</image:loc>\n    </image:image>\n",
+            escape::xml(&absolute_url(origin_url, image))
+        ));
+    }
+
+    url.push_str("  </url>");
+    url
+}
+
+/// Returns the absolute URL of a path of the site, or the percent-encoded URL if it is already absolute.
+fn absolute_url(origin_url: &str, path: &str) -> String {
+    if is_external_url(path) {
+        escape::url(path)
+    } else {
+        format!("{}{}", escape::origin(origin_url), escape::url(path))
     }
 }
@@ -0,0 +1,238 @@
+use crate::{escape, html::Scanner};
+
+/// Configuration for generating a client-side search index from the rendered pages.
+///
+/// The index is a JSON file with the path, title and visible text of each page,
+/// which can be loaded by client-side search libraries:
+///
+/// ```json
+/// {
+///   "documents": [
+///     { "path": "/about", "title": "About", "text": "About us ..." }
+///   ]
+/// }
+/// ```
+///
+/// Titles are taken from the `<title>` element of a page, or from its first `<h1>` element.
+/// Redirect pages, fallback pages and pages whose metadata sets `exclude_from_search` are left out.
+///
+/// # Examples
+///
+/// ```rust
+/// use pagebake::render::RenderConfig;
+/// use pagebake::search::SearchIndex;
+/// use pagebake::{Router, get};
+///
+/// let router = Router::new().route(
+///     "/about",
+///     get(|| "<title>About</title><h1>About &amp; contact</h1>".to_owned()),
+/// );
+///
+/// let config = RenderConfig {
+///     search_index: Some(SearchIndex::new("search-index.json")),
+///     ..Default::default()
+/// };
+///
+/// let output = router.render_to_map(config).unwrap();
+/// assert!(output.extra_files["search-index.json"].contains(r#""text": "About & contact""#));
+/// ```
+pub struct SearchIndex {
+    /// The path of the generated file, relative to the output directory.
+    pub file_name: &'static str,
+    /// Optional configuration for an OpenSearch description of the site's search page.
+    pub open_search: Option<OpenSearch>,
+}
+
+/// Configuration for an [OpenSearch](https://github.com/dewitt/opensearch) description file,
+/// which lets browsers add the site's search page as a search engine.
+pub struct OpenSearch {
+    /// The path of the generated file, relative to the output directory.
+    pub file_name: &'static str,
+    /// A short name of the search engine.
+    pub short_name: String,
+    /// A description of the search engine.
+    pub description: String,
+    /// The URL of the search page, with `{searchTerms}` in place of the query,
+    /// such as `https://example.com/search?q={searchTerms}`.
+    pub url_template: String,
+}
+
+impl SearchIndex {
+    /// Creates a `SearchIndex` configuration that writes the index to `file_name`.
+    pub fn new(file_name: &'static str) -> Self {
+        Self {
+            file_name,
+            open_search: None,
+        }
+    }
+
+    /// Also generates an `opensearch.xml` description of the search page at `url_template`.
+    pub fn open_search(
+        mut self,
+        short_name: String,
+        description: String,
+        url_template: String,
+    ) -> Self {
+        self.open_search = Some(OpenSearch {
+            file_name: "opensearch.xml",
+            short_name,
+            description,
+            url_template,
+        });
+        self
+    }
+
+    /// Renders the index, given the route paths and rendered HTML of the searchable pages.
+    pub(crate) fn render(&self, mut pages: Vec<(&str, &str)>) -> String {
+        pages.sort();
+
+        let documents = pages
+            .into_iter()
+            .map(|(path, html)| {
+                let (title, text) = extract_text(html);
+                format!(
+                    "    {{ \"path\": {}, \"title\": {}, \"text\": {} }}",
+                    escape::json(path),
+                    escape::json(&title),
+                    escape::json(&text)
+                )
+            })
+            .collect::<Vec<String>>();
+
+        if documents.is_empty() {
+            "{\n  \"documents\": []\n}".to_owned()
+        } else {
+            format!("{{\n  \"documents\": [\n{}\n  ]\n}}", documents.join(",\n"))
+        }
+    }
+}
+
+impl OpenSearch {
+    /// Renders the OpenSearch description.
+    pub(crate) fn render(&self) -> String {
+        let mut content = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+        content
+            .push_str("<OpenSearchDescription xmlns=\"http://a9.com/-/spec/opensearch/1.1/\">\n");
+        content.push_str(&format!(
+            "  <ShortName>{}</ShortName>\n  <Description>{}</Description>\n",
+            escape::xml(&self.short_name),
+            escape::xml(&self.description)
+        ));
+        content.push_str("  <InputEncoding>UTF-8</InputEncoding>\n");
+        content.push_str(&format!(
+            "  <Url type=\"text/html\" template=\"{}\"/>\n",
+            escape::xml(&self.url_template)
+        ));
+        content.push_str("</OpenSearchDescription>");
+        content
+    }
+}
+
+/// Elements whose content is not visible text.
+const HIDDEN_ELEMENTS: [&str; 5] = ["script", "style", "template", "noscript", "svg"];
+
+/// Elements that do not separate words from the surrounding text.
+const INLINE_ELEMENTS: [&str; 14] = [
+    "a", "abbr", "b", "bdi", "cite", "code", "em", "i", "mark", "q", "small", "span", "strong", "u",
+];
+
+/// Extracts the title and the visible text of an HTML page, with whitespace collapsed.
+fn extract_text(html: &str) -> (String, String) {
+    let mut scanner = Scanner::new(html);
+    let mut title: Option<String> = None;
+    let mut heading: Option<String> = None;
+    let mut text = String::new();
+    let mut in_head = false;
+
+    loop {
+        let position = scanner.position();
+        let Some(tag) = scanner.next_tag() else {
+            break;
+        };
+        if !in_head {
+            text.push_str(&html[position..tag.start]);
+        }
+
+        match tag.name.as_str() {
+            "!--" => {}
+            "head" => in_head = !tag.closing,
+            "title" if !tag.closing => {
+                let content_end = scanner.skip_to_closing("title");
+                let (_, content) = extract_text(&html[tag.end..content_end]);
+                title.get_or_insert(content);
+            }
+            "h1" if !tag.closing => {
+                let content_end = scanner.find_closing("h1");
+                let (_, content) = extract_text(&html[tag.end..content_end]);
+                heading.get_or_insert(content);
+            }
+            name if HIDDEN_ELEMENTS.contains(&name) && !tag.closing => {
+                scanner.skip_to_closing(name);
+            }
+            name if !INLINE_ELEMENTS.contains(&name) && !in_head => text.push(' '),
+            _ => {}
+        }
+    }
+    if !in_head {
+        text.push_str(&html[scanner.position()..]);
+    }
+
+    let title = title.or(heading).unwrap_or_default();
+    (title, collapse_whitespace(&decode_entities(&text)))
+}
+
+/// Decodes HTML character references, leaving unknown ones unchanged.
+fn decode_entities(text: &str) -> String {
+    let mut decoded = String::with_capacity(text.len());
+    let mut rest = text;
+
+    while let Some(start) = rest.find('&') {
+        decoded.push_str(&rest[..start]);
+        rest = &rest[start..];
+
+        let reference = rest[1..]
+            .find(';')
+            .filter(|&end| end <= 10)
+            .map(|end| &rest[1..end + 1]);
+        let character = reference.and_then(|reference| match reference {
+            "amp" => Some('&'),
+            "lt" => Some('<'),
+            "gt" => Some('>'),
+            "quot" => Some('"'),
+            "apos" => Some('\''),
+            "nbsp" => Some(' '),
+            reference => {
+                let code = if let Some(hex) = reference
+                    .strip_prefix("#x")
+                    .or_else(|| reference.strip_prefix("#X"))
+                {
+                    u32::from_str_radix(hex, 16).ok()
+                } else if let Some(decimal) = reference.strip_prefix('#') {
+                    decimal.parse().ok()
+                } else {
+                    None
+                };
+                code.and_then(char::from_u32)
+            }
+        });
+
+        match (reference, character) {
+            (Some(reference), Some(character)) => {
+                decoded.push(character);
+                rest = &rest[reference.len() + 2..];
+            }
+            _ => {
+                decoded.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+
+    decoded.push_str(rest);
+    decoded
+}
+
+/// Replaces every run of whitespace with a single space and trims the ends.
+fn collapse_whitespace(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<&str>>().join(" ")
+}